@@ -1,9 +1,32 @@
 // Importing necessary libraries and modules from external crates.
-use clap::{App, Arg, SubCommand}; // For creating and managing the command line interface.
+use chrono::NaiveDate; // To pick the date of a lunar phase.
+use clap::{App, Arg, ArgMatches, SubCommand}; // For creating and managing the command line interface.
+use clap_complete::Shell; // To generate shell completion scripts.
 use colored::*; // To add colored text in the console.
-use reqwest::blocking::Response; // To handle HTTP responses in a blocking manner.
-use reqwest::Error; // To handle errors from reqwest operations.
-use serde::Deserialize; // To enable deserialization of JSON data into Rust structures.
+use log::{debug, error, warn, Level, LevelFilter}; // To report diagnostics on stderr.
+use rand::rngs::StdRng; // To pick a random body, reproducibly when seeded.
+use rand::{Rng, SeedableRng}; // To seed the generator and draw from it.
+use reqwest::blocking::{Client, Response}; // To make HTTP requests in a blocking manner.
+use reqwest::header::{
+    HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+}; // For conditional requests and Retry-After.
+use reqwest::StatusCode; // To recognise 304 Not Modified and 429 Too Many Requests.
+use schemars::{schema_for, JsonSchema}; // To describe the JSON output as a JSON Schema.
+use serde::{Deserialize, Serialize}; // To enable (de)serialization of JSON data into Rust structures.
+use std::cmp::Ordering; // To sort bodies by a chosen field.
+use std::collections::{HashMap, HashSet}; // To spot duplicate ids and count bodies per type.
+use std::fs; // To read and write the on-disk cache.
+use std::io::{BufRead, IsTerminal, Write}; // To detect a terminal and run the interactive prompt.
+use std::path::{Path, PathBuf}; // To build cache file locations.
+use std::process::{Command, ExitCode, Stdio}; // To report the exit status and run the pager.
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering}; // For --lang and batch fetches.
+use std::time::{Duration, Instant, SystemTime}; // To express the cache time-to-live and time requests.
+
+mod physics; // Derived physical quantities such as volume and surface area.
+mod predicate; // The --where filter expressions of the listing.
+mod stats; // Aggregate statistics across the body list.
+mod units; // Conversions between metric and imperial units.
+mod validators; // Range checks for numeric command-line values.
 
 // Struct to hold the API response for multiple celestial bodies.
 #[derive(Deserialize, Debug)]
@@ -12,7 +35,7 @@ struct ApiResponse {
 }
 
 // Struct to describe a celestial body with potential fields from the API.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
 struct CelestialBody {
     name: String,
     id: String,
@@ -24,120 +47,4764 @@ struct CelestialBody {
     density: Option<f64>,
     gravity: Option<f64>,
     escape: Option<f64>,
+    #[serde(rename = "meanRadius")]
     mean_radius: Option<f64>,
+    #[serde(rename = "equaRadius")]
     equa_radius: Option<f64>,
+    #[serde(rename = "polarRadius")]
     polar_radius: Option<f64>,
     flattening: Option<f64>,
+    #[serde(rename = "semimajorAxis")]
+    semimajor_axis: Option<f64>,
+    perihelion: Option<f64>,
+    aphelion: Option<f64>,
+    #[serde(rename = "sideralOrbit")]
     sideral_orbit: Option<f64>,
+    #[serde(rename = "sideralRotation")]
     sideral_rotation: Option<f64>,
+    #[serde(rename = "axialTilt")]
     axial_tilt: Option<f64>,
+    #[serde(rename = "avgTemp")]
     avg_temp: Option<i32>,
+    #[serde(rename = "bodyType")]
     body_type: Option<String>,
+    #[serde(rename = "discoveredBy")]
+    discovered_by: Option<String>,
+    #[serde(rename = "discoveryDate")]
+    discovery_date: Option<String>,
+    moons: Option<Vec<Moon>>,
+}
+
+// Whether bodies are shown by their native name (`--lang native`) rather than in English. Set
+// once at startup, like the color override, so every formatter picks the same name.
+static NATIVE_NAMES: AtomicBool = AtomicBool::new(false);
+
+impl CelestialBody {
+    // Function to return the name to display for this body, following --lang.
+    fn display_name(&self) -> &str {
+        if NATIVE_NAMES.load(AtomicOrdering::Relaxed) && !self.name.trim().is_empty() {
+            &self.name
+        } else {
+            &self.english_name
+        }
+    }
 }
 
 // Struct to describe mass, accommodating optional fields for mass value and exponent.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
 struct Mass {
+    #[serde(rename = "massValue")]
     mass_value: Option<f64>,
+    #[serde(rename = "massExponent")]
     mass_exponent: Option<i32>,
 }
 
-// Function to fetch a list of all celestial bodies from the API.
-fn fetch_celestial_bodies() -> Result<Vec<CelestialBody>, Error> {
-    let url = "https://api.le-systeme-solaire.net/rest/bodies/";
-    let response: Response = reqwest::blocking::get(url)?;
-    let api_response: ApiResponse = response.json()?;
+// Struct to describe a moon reference as listed on its planet, with a link to its own record.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+struct Moon {
+    moon: String,
+    rel: String,
+}
+
+// Error returned when the API answers but the answer is not a usable body record.
+#[derive(Debug)]
+enum FetchError {
+    // No body exists under the requested name; carries the closest known name, if any.
+    NotFound {
+        name: String,
+        suggestion: Option<String>,
+    },
+    // The API responded with a 5xx status.
+    ServerError(StatusCode),
+    // The API kept answering 429 Too Many Requests after every retry.
+    RateLimited,
+    // The response had an unexpected status or could not be parsed as body data.
+    UnexpectedContent(String),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FetchError::NotFound {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "No celestial body named '{}' exists. Did you mean '{}'?",
+                name, suggestion
+            ),
+            FetchError::NotFound { name, .. } => {
+                write!(f, "No celestial body named '{}' exists.", name)
+            }
+            FetchError::ServerError(status) => write!(
+                f,
+                "The API reported a server error ({}); try again later.",
+                status
+            ),
+            FetchError::RateLimited => write!(
+                f,
+                "The API is rate limiting requests; wait a while and try again."
+            ),
+            FetchError::UnexpectedContent(detail) => {
+                write!(f, "The API returned unexpected content: {}", detail)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+// Error returned by a batch lookup when some of the requested bodies could not be fetched.
+#[derive(Debug)]
+struct PartialFailure {
+    failed: Vec<String>,
+    // Whether every failure was an unknown name, which keeps the "not found" exit code.
+    all_not_found: bool,
+}
+
+impl std::fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Could not fetch details for: {}", self.failed.join(", "))
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+// Error returned by `details --strict` when a requested field is absent from the API record.
+#[derive(Debug)]
+struct MissingFields {
+    // Each entry is "<body>: <field>, <field>".
+    bodies: Vec<String>,
+}
+
+impl std::fmt::Display for MissingFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Missing fields for {}", self.bodies.join("; "))
+    }
+}
+
+impl std::error::Error for MissingFields {}
+
+// Function to check a response's status before its body is deserialized. A 404 means the
+// named body does not exist; any other non-success status is reported as such.
+fn check_status(response: Response, name: Option<&str>) -> Result<Response, FetchError> {
+    let status = response.status();
+    match name {
+        _ if status.is_success() => Ok(response),
+        Some(name) if status == StatusCode::NOT_FOUND => Err(FetchError::NotFound {
+            name: name.to_string(),
+            suggestion: None,
+        }),
+        _ if status.is_server_error() => Err(FetchError::ServerError(status)),
+        _ if status == StatusCode::TOO_MANY_REQUESTS => Err(FetchError::RateLimited),
+        _ => Err(FetchError::UnexpectedContent(format!(
+            "unexpected HTTP status {}",
+            status
+        ))),
+    }
+}
+
+// Reference values for Earth used to express other bodies in Earth-relative units.
+struct EarthReference {
+    mass_kg: f64,
+    mean_radius_km: f64,
+    density: f64,
+    gravity: f64,
+    escape: f64,
+    sideral_orbit_days: f64,
+    avg_temp: f64,
+}
+
+const EARTH: EarthReference = EarthReference {
+    mass_kg: 5.97237e24,
+    mean_radius_km: 6371.0084,
+    density: 5.5136,
+    gravity: 9.8,
+    escape: 11190.0,
+    sideral_orbit_days: 365.256,
+    avg_temp: 288.0,
+};
+
+// Unit system used when printing measurements.
+#[derive(Default, PartialEq)]
+enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+// Temperature scale used when printing the average temperature.
+#[derive(Default)]
+enum TemperatureUnit {
+    #[default]
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    // Function to convert a Kelvin temperature into this unit, returning the value and symbol.
+    fn convert(&self, kelvin: i32) -> (f64, &'static str) {
+        match self {
+            TemperatureUnit::Kelvin => (f64::from(kelvin), "K"),
+            TemperatureUnit::Celsius => (units::kelvin_to_celsius(kelvin), "°C"),
+            TemperatureUnit::Fahrenheit => (units::kelvin_to_fahrenheit(kelvin), "°F"),
+        }
+    }
+}
+
+// Options controlling how the details view renders values.
+#[derive(Default)]
+struct DisplayOptions {
+    relative_to_earth: bool,
+    units: UnitSystem,
+    temp_unit: TemperatureUnit,
+    quiet: bool,
+    // With --strict, absent fields print as MISSING instead of a placeholder value.
+    strict: bool,
+    // Number of decimal places floats are rounded to.
+    precision: usize,
+    // Thousands separator for --group-digits/--locale; None prints digits ungrouped.
+    digit_separator: Option<char>,
+}
+
+// Function to locate the cache directory, honouring XDG_CACHE_HOME before falling back to ~/.cache.
+fn cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("solar_system_map_cli"))
+}
+
+// Function to get the path of the saved snapshot of a body, under XDG_DATA_HOME or
+// ~/.local/share since snapshots are kept on purpose rather than being a cache.
+fn snapshot_path(id: &str) -> Option<PathBuf> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .map(|dir| {
+            dir.join("solar_system_map_cli")
+                .join("snapshots")
+                .join(format!("{}.json", id))
+        })
+}
+
+// Struct to hold the settings read from the config file. Every key mirrors a command-line flag
+// and is optional; flags given on the command line override them.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    format: Option<String>,
+    retries: Option<u32>,
+    timeout: Option<u64>,
+    connect_timeout: Option<u64>,
+    api_base_url: Option<String>,
+    cache_ttl: Option<u64>,
+    units: Option<String>,
+    temp_unit: Option<String>,
+}
+
+// Values accepted by --format, --units and --temp-unit, shared with the config file checks.
+const FORMATS: [&str; 10] = [
+    "text", "json", "jsonl", "yaml", "gron", "xml", "csv", "tsv", "markdown", "table",
+];
+const UNIT_SYSTEMS: [&str; 2] = ["metric", "imperial"];
+const TEMP_UNITS: [&str; 3] = ["kelvin", "celsius", "fahrenheit"];
+
+impl Config {
+    // Function to check the config values that clap will not see until they are used as
+    // defaults, so a typo is reported instead of tripping clap's own checks.
+    fn validate(&self) -> Result<(), String> {
+        let check = |key: &str, value: &Option<String>, allowed: &[&str]| match value {
+            Some(value) if !allowed.contains(&value.as_str()) => Err(format!(
+                "invalid {} '{}', expected one of: {}",
+                key,
+                value,
+                allowed.join(", ")
+            )),
+            _ => Ok(()),
+        };
+        check("format", &self.format, &FORMATS)?;
+        check("units", &self.units, &UNIT_SYSTEMS)?;
+        check("temp-unit", &self.temp_unit, &TEMP_UNITS)?;
+        let number =
+            |key: &str, value: Option<u64>, valid: fn(&str) -> Result<(), String>| match value {
+                Some(value) => {
+                    valid(&value.to_string()).map_err(|e| format!("invalid {}: {}", key, e))
+                }
+                None => Ok(()),
+            };
+        number("retries", self.retries.map(u64::from), validators::retries)?;
+        number("timeout", self.timeout, validators::timeout)?;
+        number("connect-timeout", self.connect_timeout, validators::timeout)?;
+        match &self.api_base_url {
+            Some(url) => validate_base_url(url),
+            None => Ok(()),
+        }
+    }
+}
+
+// Function to get the path of the config file, honouring XDG_CONFIG_HOME.
+fn config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("solar_system_map_cli").join("config.toml"))
+}
+
+// Function to load the config file. A missing file means no settings; an unreadable one is
+// reported on stderr and otherwise ignored, and so is each unknown or invalid key in it.
+fn load_config() -> Config {
+    let Some(path) = config_path().filter(|path| path.exists()) else {
+        return Config::default();
+    };
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| parse_config(&raw))
+    {
+        Ok((config, problems)) => {
+            for problem in problems {
+                warn!("Ignoring config key in {}: {}", path.display(), problem);
+            }
+            config
+        }
+        Err(e) => {
+            warn!("Ignoring invalid config file {}: {}", path.display(), e);
+            Config::default()
+        }
+    }
+}
+
+// Function to parse the config file text key by key, keeping the valid keys and returning a
+// message for each one that had to be dropped.
+fn parse_config(raw: &str) -> Result<(Config, Vec<String>), String> {
+    let from_table = |table: toml::Table| {
+        toml::Value::Table(table)
+            .try_into::<Config>()
+            .map_err(|e| e.to_string().trim().to_string())
+            .and_then(|config| config.validate().map(|()| config))
+    };
+    let table = raw.parse::<toml::Table>().map_err(|e| e.to_string())?;
+    let mut valid = toml::Table::new();
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        let single = toml::Table::from_iter([(key.clone(), value.clone())]);
+        match from_table(single) {
+            Ok(_) => {
+                valid.insert(key, value);
+            }
+            Err(e) => problems.push(format!("{}: {}", key, e)),
+        }
+    }
+    Ok((from_table(valid)?, problems))
+}
+
+// Function to give an argument a default taken from the config file, when one is set.
+fn config_default<'a>(arg: Arg<'a>, value: Option<&'a str>) -> Arg<'a> {
+    match value {
+        Some(value) => arg.default_value(value),
+        None => arg,
+    }
+}
+
+// Function to get the path of the cached full body list.
+fn bodies_cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("bodies.json"))
+}
+
+// Function to get the path of the cached details of one body, keyed by its id. Ids that could
+// escape the cache directory are never cached.
+fn cached_detail_path(id: &str) -> Option<PathBuf> {
+    let safe = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !safe {
+        return None;
+    }
+    cache_dir().map(|dir| dir.join("bodies").join(format!("{}.json", id)))
+}
+
+// Function to load a body from its cached details, if they exist and are within the
+// time-to-live. Any age is accepted offline; without a TTL the details are refetched.
+fn read_cached_detail(api: &ReqwestProvider, id: &str) -> Option<CelestialBody> {
+    let path = cached_detail_path(id)?;
+    let usable = api.offline || api.cache_ttl.is_some_and(|ttl| is_cache_fresh(&path, ttl));
+    if !usable {
+        return None;
+    }
+    let raw = fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+// Function to store a body's details under its id; failures only cost a later download.
+fn write_cached_detail(body: &CelestialBody) {
+    if let Some(path) = cached_detail_path(&body.id) {
+        let written = serde_json::to_string(body)
+            .map_err(|e| e.to_string())
+            .and_then(|json| write_cache(&path, &json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Unable to write cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+// Function to check whether a cached file was written less than `ttl` ago.
+fn is_cache_fresh(path: &Path, ttl: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < ttl)
+}
+
+// Function to load the body list from a cached copy of the API response.
+fn read_cached_bodies(path: &Path) -> Result<Vec<CelestialBody>, Box<dyn std::error::Error>> {
+    let raw = fs::read_to_string(path)?;
+    let api_response: ApiResponse = serde_json::from_str(&raw)?;
     Ok(api_response.bodies)
 }
 
-// Function to fetch detailed information about a specific celestial body by name.
-fn fetch_celestial_body_details(name: &str) -> Result<CelestialBody, Error> {
-    let url = format!("https://api.le-systeme-solaire.net/rest/bodies/{}", name);
-    let response: Response = reqwest::blocking::get(&url)?;
-    response.json::<CelestialBody>()
+// Struct to hold the HTTP cache validators of the cached body list, stored next to it so that
+// later fetches can ask the API whether the list has changed.
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
 }
 
-// The main function sets up the command-line interface and processes user input.
-fn main() {
-    let matches = App::new("Solar System Explorer")
-        .version("0.1.0")
-        .author("Your Name <your_email@example.com>")
-        .about("Displays information about planets and other bodies in the solar system")
-        .subcommand(
-            SubCommand::with_name("details")
-                .about("Displays detailed information about a specific celestial body")
-                .arg(
-                    Arg::with_name("name")
-                        .help("The name of the celestial body to fetch details for")
-                        .required(true)
-                        .index(1),
-                ),
-        )
-        .get_matches();
-
-    if let Some(matches) = matches.subcommand_matches("details") {
-        if let Some(name) = matches.value_of("name") {
-            match fetch_celestial_body_details(name) {
-                Ok(body) => {
-                    println!(
-                        "{}: {}, {}: {}, {}: {}, {}: {}",
-                        "Name".green().bold(),
-                        body.name,
-                        "ID".green().bold(),
-                        body.id,
-                        "English Name".green().bold(),
-                        body.english_name,
-                        "Is Planet".green().bold(),
-                        body.is_planet.to_string().blue()
-                    );
-                    if let Some(mass) = &body.mass {
-                        if let (Some(value), Some(exponent)) = (mass.mass_value, mass.mass_exponent)
-                        {
-                            println!("{}: {}e{}", "Mass".yellow().bold(), value, exponent);
-                        } else {
-                            println!("{}", "Mass data is incomplete or not available.".red());
-                        }
-                    } else {
-                        println!("{}", "No mass data provided by the API.".red());
-                    }
-                    println!("Density: {} g/cm³", body.density.unwrap_or(0.0));
-                    println!("Gravity: {} m/s²", body.gravity.unwrap_or(0.0));
-                    println!("Escape Velocity: {} m/s", body.escape.unwrap_or(0.0));
-                    println!("Mean Radius: {} km", body.mean_radius.unwrap_or(0.0));
-                    println!("Equatorial Radius: {} km", body.equa_radius.unwrap_or(0.0));
-                    println!("Polar Radius: {} km", body.polar_radius.unwrap_or(0.0));
-                    println!("Flattening: {}", body.flattening.unwrap_or(0.0));
-                    println!("Orbital Period: {} days", body.sideral_orbit.unwrap_or(0.0));
-                    println!(
-                        "Rotation Period: {} hours",
-                        body.sideral_rotation.unwrap_or(0.0)
-                    );
-                    println!("Axial Tilt: {} degrees", body.axial_tilt.unwrap_or(0.0));
-                    println!("Average Temperature: {} K", body.avg_temp.unwrap_or(0));
-                    println!(
-                        "Body Type: {}",
-                        body.body_type.as_deref().unwrap_or("Not specified")
+// Function to get the path of the validators stored alongside the cached body list.
+fn validators_cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("bodies.validators.json"))
+}
+
+// Function to read the stored validators, treating a missing or unreadable file as none.
+fn read_validators(path: &Path) -> CacheValidators {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+// Function to collect the ETag and Last-Modified headers of a response.
+fn response_validators(response: &Response) -> CacheValidators {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    CacheValidators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    }
+}
+
+// Function to turn stored validators into If-None-Match / If-Modified-Since request headers.
+fn conditional_headers(validators: &CacheValidators) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = validators.etag.as_deref().and_then(|v| v.parse().ok()) {
+        headers.insert(IF_NONE_MATCH, value);
+    }
+    if let Some(value) = validators
+        .last_modified
+        .as_deref()
+        .and_then(|v| v.parse().ok())
+    {
+        headers.insert(IF_MODIFIED_SINCE, value);
+    }
+    headers
+}
+
+// Function to store the raw API response, creating the cache directory if needed.
+fn write_cache(path: &Path, raw: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, raw)
+}
+
+// Function to set a cached file's modification time to now without rewriting it.
+fn touch_cache(path: &Path) -> std::io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+// Trait for anything that can supply celestial bodies, so the command handlers can run against
+// the live API or against canned data. Providers are shared between the threads of a batch fetch.
+trait BodyProvider: Sync {
+    fn get_all(&self) -> Result<Vec<CelestialBody>, Box<dyn std::error::Error>>;
+    fn get_one(&self, name: &str) -> Result<CelestialBody, Box<dyn std::error::Error>>;
+
+    // Like `get_one`, but skipping any cached copy that could be out of date.
+    fn get_fresh(&self, name: &str) -> Result<CelestialBody, Box<dyn std::error::Error>> {
+        self.get_one(name)
+    }
+}
+
+// User-Agent sent with every request unless --user-agent overrides it.
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+// Struct to hold the settings the shared HTTP client is built with.
+struct ClientSettings {
+    timeout: Duration,
+    connect_timeout: Duration,
+    user_agent: String,
+}
+
+impl ClientSettings {
+    // Function to read the client settings from the command line.
+    fn from_matches(matches: &ArgMatches) -> ClientSettings {
+        ClientSettings {
+            timeout: Duration::from_secs(matches.value_of_t("timeout").unwrap_or(30)),
+            connect_timeout: Duration::from_secs(
+                matches.value_of_t("connect-timeout").unwrap_or(10),
+            ),
+            user_agent: matches
+                .value_of("user-agent")
+                .unwrap_or(DEFAULT_USER_AGENT)
+                .to_string(),
+        }
+    }
+}
+
+// Struct to hold the HTTP client and request settings shared by every API call.
+struct ReqwestProvider {
+    client: Client,
+    retries: u32,
+    timeout: Duration,
+    connect_timeout: Duration,
+    offline: bool,
+    cache_ttl: Option<Duration>,
+    base_url: String,
+    // Whether to show a "Fetching bodies..." message on stderr while the full list downloads;
+    // off under --quiet and --verbose.
+    show_progress: bool,
+}
+
+// Struct to show a one-line status message on stderr that is cleared again when dropped.
+struct ProgressMessage {
+    visible: bool,
+}
+
+impl ProgressMessage {
+    // Function to print the message, unless progress output is disabled.
+    fn show(message: &str, visible: bool) -> ProgressMessage {
+        if visible {
+            eprint!("{}", message);
+        }
+        ProgressMessage { visible }
+    }
+}
+
+impl Drop for ProgressMessage {
+    // Function to erase the message by returning to the start of the line and clearing it.
+    fn drop(&mut self) {
+        if self.visible {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+impl ReqwestProvider {
+    // Function to build the shared client once, with the configured timeouts and User-Agent.
+    fn new(
+        settings: ClientSettings,
+        retries: u32,
+        offline: bool,
+        cache_ttl: Option<Duration>,
+        base_url: &str,
+        show_progress: bool,
+    ) -> Result<ReqwestProvider, reqwest::Error> {
+        let client = Client::builder()
+            .timeout(settings.timeout)
+            .connect_timeout(settings.connect_timeout)
+            .user_agent(settings.user_agent)
+            .build()?;
+        Ok(ReqwestProvider {
+            client,
+            retries,
+            timeout: settings.timeout,
+            connect_timeout: settings.connect_timeout,
+            offline,
+            cache_ttl,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            show_progress,
+        })
+    }
+
+    // Function to build the URL of an API path relative to the configured base.
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    // Function to turn a reqwest error into a user-facing error, spelling out which timeout hit.
+    fn describe_error(&self, error: reqwest::Error) -> Box<dyn std::error::Error> {
+        match error.status() {
+            _ if error.is_timeout() && error.is_connect() => format!(
+                "Connection timed out after {} seconds",
+                self.connect_timeout.as_secs()
+            )
+            .into(),
+            _ if error.is_timeout() => {
+                format!("Request timed out after {} seconds", self.timeout.as_secs()).into()
+            }
+            Some(status) if status.is_server_error() => Box::new(FetchError::ServerError(status)),
+            _ if error.is_decode() => Box::new(FetchError::UnexpectedContent(error.to_string())),
+            _ => error.into(),
+        }
+    }
+}
+
+// Function to read how long a 429 response asks us to wait. Only the delay-in-seconds form
+// of Retry-After is understood; an HTTP date falls back to the normal backoff.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+// Function to GET a URL, retrying network failures and 5xx responses up to the configured
+// number of times with exponential backoff, and 429 responses after the wait the API asks for.
+// A 429 that asks for longer than the request timeout is returned as is, and so are other client
+// errors such as 404.
+fn get_with_retry(
+    api: &ReqwestProvider,
+    url: &str,
+    headers: HeaderMap,
+) -> Result<Response, reqwest::Error> {
+    let retries = api.retries;
+    let mut attempt = 0;
+    loop {
+        debug!("GET {}", url);
+        let started = Instant::now();
+        let result = api.client.get(url).headers(headers.clone()).send();
+        let elapsed = started.elapsed().as_millis();
+        match &result {
+            Ok(response) => debug!("{} in {} ms", response.status(), elapsed),
+            Err(e) => debug!("failed after {} ms: {}", elapsed, e),
+        }
+        let result = match result {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                if attempt >= retries {
+                    return Ok(response);
+                }
+                attempt += 1;
+                let wait = retry_after(&response)
+                    .unwrap_or_else(|| Duration::from_millis(500 * 2u64.pow(attempt - 1)));
+                if wait > api.timeout {
+                    warn!(
+                        "rate limited by the API, which asks for a {} s wait; not retrying",
+                        wait.as_secs()
                     );
+                    return Ok(response);
                 }
-                Err(e) => println!("{} {}: {}", "Error fetching details for".red(), name, e),
+                warn!(
+                    "rate limited by the API; waiting {:.1} s before retrying ({}/{})...",
+                    wait.as_secs_f64(),
+                    attempt,
+                    retries
+                );
+                std::thread::sleep(wait);
+                continue;
+            }
+            result => result,
+        };
+        let result = result.and_then(|response| {
+            if response.status().is_server_error() {
+                response.error_for_status()
+            } else {
+                Ok(response)
+            }
+        });
+        match result {
+            Err(e) if attempt < retries && e.status().is_none_or(|s| s.is_server_error()) => {
+                attempt += 1;
+                warn!("retrying ({}/{})...", attempt, retries);
+                std::thread::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1)));
+            }
+            result => return result,
+        }
+    }
+}
+
+// Function to fetch a list of all celestial bodies from the API, or from the cache when
+// running offline or when the cached copy is still within its time-to-live.
+fn fetch_celestial_bodies(
+    api: &ReqwestProvider,
+) -> Result<Vec<CelestialBody>, Box<dyn std::error::Error>> {
+    let cache_path = bodies_cache_path();
+    if api.offline {
+        let path = cache_path.ok_or("Unable to determine the cache directory")?;
+        if !path.exists() {
+            return Err(format!(
+                "No cached body list found at {}; run once without --offline first",
+                path.display()
+            )
+            .into());
+        }
+        return read_cached_bodies(&path);
+    }
+    if let (Some(path), Some(ttl)) = (&cache_path, api.cache_ttl) {
+        if is_cache_fresh(path, ttl) {
+            if let Ok(bodies) = read_cached_bodies(path) {
+                return Ok(bodies);
+            }
+        }
+    }
+
+    let url = api.url("bodies/");
+    let progress = ProgressMessage::show("Fetching bodies...", api.show_progress);
+    // Only revalidate when there is a cached list to fall back on.
+    let validators_path = validators_cache_path();
+    let headers = match (&cache_path, &validators_path) {
+        (Some(path), Some(validators)) if path.exists() => {
+            conditional_headers(&read_validators(validators))
+        }
+        _ => HeaderMap::new(),
+    };
+    let response = get_with_retry(api, &url, headers).map_err(|e| api.describe_error(e))?;
+    if let (StatusCode::NOT_MODIFIED, Some(path)) = (response.status(), &cache_path) {
+        // Bumping the unchanged files' modification time restarts the time-to-live, so later
+        // runs do not revalidate again until it runs out.
+        let bodies = read_cached_bodies(path)?;
+        for path in std::iter::once(path).chain(&validators_path) {
+            if let Err(e) = touch_cache(path) {
+                warn!("Unable to refresh cache {}: {}", path.display(), e);
             }
         }
+        return Ok(bodies);
+    }
+    let response = check_status(response, None)?;
+    let validators = response_validators(&response);
+    let raw = response.text().map_err(|e| api.describe_error(e))?;
+    let api_response: ApiResponse =
+        serde_json::from_str(&raw).map_err(|e| FetchError::UnexpectedContent(e.to_string()))?;
+    drop(progress);
+    if let Some(path) = &cache_path {
+        if let Err(e) = write_cache(path, &raw) {
+            warn!("Unable to write cache {}: {}", path.display(), e);
+        } else if let Some(validators_path) = &validators_path {
+            // Losing the validators only costs a full download next time, so a failed write
+            // is not worth reporting.
+            let _ =
+                serde_json::to_string(&validators).map(|json| write_cache(validators_path, &json));
+        }
+    }
+    Ok(api_response.bodies)
+}
+
+// Function to load the cached body list for detail lookups, if one exists and is within the
+// time-to-live. Without a TTL the list is refetched, as for the listing, unless offline.
+fn cached_bodies_for_lookup(api: &ReqwestProvider) -> Option<Vec<CelestialBody>> {
+    let path = bodies_cache_path()?;
+    let usable = api.offline || api.cache_ttl.is_some_and(|ttl| is_cache_fresh(&path, ttl));
+    if usable && path.exists() {
+        read_cached_bodies(&path).ok()
     } else {
-        match fetch_celestial_bodies() {
-            Ok(bodies) => {
-                for body in bodies {
-                    println!(
-                        "{}: {}, {}: {}, {}: {}",
-                        "Name".green().bold(),
-                        body.name,
-                        "ID".green().bold(),
-                        body.id,
-                        "Is Planet".green().bold(),
-                        body.is_planet.to_string().blue()
-                    );
+        None
+    }
+}
+
+// Function to fetch detailed information about a specific celestial body by name or id. Cached
+// copies are used first; then the API is asked for the input as given, and when it does not
+// know it, the input is resolved against the full list by id or English name, which also lets
+// typos produce a "Did you mean" suggestion instead of the raw API error. Bodies fetched from
+// the API are cached one by one, so a later lookup by id needs no list at all.
+fn fetch_celestial_body_details(
+    api: &ReqwestProvider,
+    name: &str,
+) -> Result<CelestialBody, Box<dyn std::error::Error>> {
+    if let Some(bodies) = cached_bodies_for_lookup(api) {
+        if let Some(body) = find_body_in_list(name, &bodies) {
+            return Ok(body.clone());
+        }
+    }
+    if let Some(body) = read_cached_detail(api, &normalize_body_name(name)) {
+        return Ok(body);
+    }
+    if let Some(known) = obvious_typo_of(name) {
+        return Err(Box::new(FetchError::NotFound {
+            name: name.to_string(),
+            suggestion: Some(known.to_string()),
+        }));
+    }
+    if api.offline {
+        let bodies = fetch_celestial_bodies(api)?;
+        return Ok(resolve_in_list(name, &bodies)?.clone());
+    }
+
+    let url = api.url(&format!("bodies/{}", normalize_body_name(name)));
+    let response =
+        get_with_retry(api, &url, HeaderMap::new()).map_err(|e| api.describe_error(e))?;
+    let body = match check_status(response, Some(name)) {
+        Ok(response) => {
+            let raw = response.text().map_err(|e| api.describe_error(e))?;
+            serde_json::from_str::<CelestialBody>(&raw)
+                .map_err(|e| FetchError::UnexpectedContent(e.to_string()))?
+        }
+        Err(FetchError::NotFound { .. }) => {
+            let bodies = fetch_celestial_bodies(api)?;
+            resolve_in_list(name, &bodies)?.clone()
+        }
+        Err(e) => return Err(Box::new(e)),
+    };
+    write_cached_detail(&body);
+    Ok(body)
+}
+
+impl BodyProvider for ReqwestProvider {
+    fn get_all(&self) -> Result<Vec<CelestialBody>, Box<dyn std::error::Error>> {
+        fetch_celestial_bodies(self)
+    }
+
+    fn get_one(&self, name: &str) -> Result<CelestialBody, Box<dyn std::error::Error>> {
+        fetch_celestial_body_details(self, name)
+    }
+
+    // A zero time-to-live makes every cached copy stale, so the API is asked again.
+    fn get_fresh(&self, name: &str) -> Result<CelestialBody, Box<dyn std::error::Error>> {
+        let uncached = ReqwestProvider {
+            client: self.client.clone(),
+            retries: self.retries,
+            timeout: self.timeout,
+            connect_timeout: self.connect_timeout,
+            offline: self.offline,
+            cache_ttl: Some(Duration::ZERO),
+            base_url: self.base_url.clone(),
+            show_progress: self.show_progress,
+        };
+        fetch_celestial_body_details(&uncached, name)
+    }
+}
+
+// Struct to serve a fixed list of bodies without touching the network.
+struct MockProvider {
+    bodies: Vec<CelestialBody>,
+}
+
+// A few bodies bundled into the binary for --mock, in the same shape as the API's body list.
+const MOCK_FIXTURE: &str = include_str!("../fixtures/bodies.json");
+
+impl MockProvider {
+    // Function to build a provider serving the bundled fixture.
+    fn from_fixture() -> Result<MockProvider, serde_json::Error> {
+        let api_response: ApiResponse = serde_json::from_str(MOCK_FIXTURE)?;
+        Ok(MockProvider {
+            bodies: api_response.bodies,
+        })
+    }
+}
+
+impl BodyProvider for MockProvider {
+    fn get_all(&self) -> Result<Vec<CelestialBody>, Box<dyn std::error::Error>> {
+        Ok(self.bodies.clone())
+    }
+
+    fn get_one(&self, name: &str) -> Result<CelestialBody, Box<dyn std::error::Error>> {
+        Ok(resolve_in_list(name, &self.bodies)?.clone())
+    }
+}
+
+// Function to fetch several bodies at once on up to `jobs` threads, returning one result per
+// name in the order given. A failed lookup does not stop the others.
+fn fetch_many(
+    provider: &dyn BodyProvider,
+    names: &[&str],
+    jobs: usize,
+) -> Vec<Result<CelestialBody, Box<dyn std::error::Error>>> {
+    // Errors are not Send, so workers keep FetchError intact (its exit code matters) and flatten
+    // everything else to its message.
+    enum Failure {
+        Fetch(FetchError),
+        Other(String),
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Result<CelestialBody, Failure>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.clamp(1, names.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, AtomicOrdering::Relaxed);
+                        let Some(name) = names.get(index) else {
+                            break done;
+                        };
+                        let result =
+                            provider
+                                .get_one(name)
+                                .map_err(|e| match e.downcast::<FetchError>() {
+                                    Ok(e) => Failure::Fetch(*e),
+                                    Err(e) => Failure::Other(e.to_string()),
+                                });
+                        done.push((index, result));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("fetch worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results
+        .into_iter()
+        .map(|(_, result)| {
+            result.map_err(|failure| -> Box<dyn std::error::Error> {
+                match failure {
+                    Failure::Fetch(e) => Box::new(e),
+                    Failure::Other(message) => message.into(),
                 }
-            }
-            Err(e) => println!("{}: {}", "Error fetching data".red(), e),
+            })
+        })
+        .collect()
+}
+
+// Names and ids of the major planets, dwarf planets, the Sun and the Moon, used to catch
+// obvious typos without a network round trip.
+const KNOWN_BODY_NAMES: [&str; 26] = [
+    "sun", "soleil", "mercury", "mercure", "venus", "earth", "terre", "moon", "lune", "mars",
+    "jupiter", "saturn", "saturne", "uranus", "neptune", "pluto", "pluton", "ceres", "eris",
+    "haumea", "makemake", "gonggong", "quaoar", "sedna", "orcus", "salacia",
+];
+
+// Function to put a body name typed by the user into the form used by the API's ids.
+fn normalize_body_name(input: &str) -> String {
+    input.trim().to_lowercase()
+}
+
+// Function to spot a name that is one edit away from a well-known body. Short names are left
+// alone, since many small bodies (Eros and Eris, say) differ from a major one by a letter.
+fn obvious_typo_of(input: &str) -> Option<&'static str> {
+    let name = normalize_body_name(input);
+    if name.chars().count() < 5 || KNOWN_BODY_NAMES.contains(&name.as_str()) {
+        return None;
+    }
+    KNOWN_BODY_NAMES
+        .iter()
+        .find(|known| levenshtein(&name, known) == 1)
+        .copied()
+}
+
+// Function to compute the Levenshtein edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b_chars.len()]
+}
+
+// Function to check whether a query names a body exactly, by English name, native name or ID,
+// ignoring case and surrounding whitespace.
+fn is_exact_match(query: &str, body: &CelestialBody) -> bool {
+    let query = query.trim();
+    body.english_name.eq_ignore_ascii_case(query)
+        || body.name.eq_ignore_ascii_case(query)
+        || body.id.eq_ignore_ascii_case(query)
+}
+
+// Function to find a body in an already loaded list by its exact name or ID.
+fn find_body_in_list<'a>(name: &str, bodies: &'a [CelestialBody]) -> Option<&'a CelestialBody> {
+    bodies.iter().find(|body| is_exact_match(name, body))
+}
+
+// Function to find the body whose English name is closest to the query. An exact match is
+// returned immediately; otherwise the nearest name within a few edits is suggested.
+fn find_closest_body<'a>(query: &str, bodies: &'a [CelestialBody]) -> Option<&'a CelestialBody> {
+    if let Some(exact) = find_body_in_list(query, bodies) {
+        return Some(exact);
+    }
+    let query = query.to_lowercase();
+    let max_distance = (query.chars().count() / 3).max(2);
+    bodies
+        .iter()
+        .map(|body| (levenshtein(&query, &body.english_name.to_lowercase()), body))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, body)| body)
+}
+
+// Function to resolve a name, id or English name against a loaded list, the one place lookups
+// and fuzzy suggestions are decided. An unknown name comes back as NotFound, with the nearest
+// English name as a suggestion when there is one.
+fn resolve_in_list<'a>(
+    name: &str,
+    bodies: &'a [CelestialBody],
+) -> Result<&'a CelestialBody, FetchError> {
+    find_body_in_list(name, bodies).ok_or_else(|| FetchError::NotFound {
+        name: name.to_string(),
+        suggestion: find_closest_body(name, bodies).map(|b| b.english_name.clone()),
+    })
+}
+
+// Function to keep only the bodies the API flags as planets.
+fn filter_planets(bodies: Vec<CelestialBody>) -> Vec<CelestialBody> {
+    bodies.into_iter().filter(|body| body.is_planet).collect()
+}
+
+// Function to drop bodies with a blank name and every repeat of an id already seen, keeping the
+// first occurrence of each.
+fn dedupe_bodies(bodies: Vec<CelestialBody>) -> Vec<CelestialBody> {
+    let mut seen = HashSet::new();
+    bodies
+        .into_iter()
+        .filter(|body| !body.name.trim().is_empty() && seen.insert(body.id.clone()))
+        .collect()
+}
+
+// Function to count bodies per type, most common first and ties in name order; bodies without a
+// type are counted as "Unknown".
+fn summarize_by_type(bodies: &[CelestialBody]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for body in bodies {
+        let kind = body.body_type.as_deref().unwrap_or("Unknown");
+        *counts.entry(kind.to_string()).or_default() += 1;
+    }
+    let mut summary: Vec<(String, usize)> = counts.into_iter().collect();
+    summary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary
+}
+
+// Function to keep only the bodies whose mass in kg lies within the given inclusive bounds;
+// bodies without usable mass data are dropped.
+fn filter_by_mass(
+    bodies: Vec<CelestialBody>,
+    min_kg: Option<f64>,
+    max_kg: Option<f64>,
+) -> Vec<CelestialBody> {
+    bodies
+        .into_iter()
+        .filter(|body| {
+            mass_magnitude(body).is_some_and(|kg| {
+                min_kg.is_none_or(|min| kg >= min) && max_kg.is_none_or(|max| kg <= max)
+            })
+        })
+        .collect()
+}
+
+// Function to keep only the bodies whose type matches, ignoring case.
+fn filter_by_type(bodies: Vec<CelestialBody>, body_type: &str) -> Vec<CelestialBody> {
+    bodies
+        .into_iter()
+        .filter(|body| {
+            body.body_type
+                .as_deref()
+                .is_some_and(|kind| kind.eq_ignore_ascii_case(body_type))
+        })
+        .collect()
+}
+
+// Function to list the distinct body types present in the data, sorted alphabetically.
+fn body_types(bodies: &[CelestialBody]) -> Vec<&str> {
+    let mut types: Vec<&str> = bodies
+        .iter()
+        .filter_map(|body| body.body_type.as_deref())
+        .collect();
+    types.sort_unstable();
+    types.dedup();
+    types
+}
+
+// Field used to order the body listing.
+#[derive(Clone, Copy)]
+enum SortField {
+    Name,
+    Mass,
+    Radius,
+    Gravity,
+    Distance,
+}
+
+// Names accepted by --sort-by and --sort-columns-by.
+const SORT_FIELDS: [&str; 5] = ["name", "mass", "radius", "gravity", "distance"];
+
+// Function to map a --sort-by name onto its field, defaulting to the name.
+fn sort_field(name: &str) -> SortField {
+    match name {
+        "mass" => SortField::Mass,
+        "radius" => SortField::Radius,
+        "gravity" => SortField::Gravity,
+        "distance" => SortField::Distance,
+        _ => SortField::Name,
+    }
+}
+
+// Function to order two optional values, always placing missing values last.
+fn compare_optional(a: Option<f64>, b: Option<f64>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.total_cmp(&a),
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// Function to sort bodies by the given field; bodies missing that field, which the API reports
+// as 0, end up last in either direction. Ties are broken by name and then id, so the order
+// never depends on the API's.
+fn sort_bodies(bodies: &mut [CelestialBody], field: SortField, descending: bool) {
+    let by_name = |a: &CelestialBody, b: &CelestialBody| {
+        a.display_name()
+            .to_lowercase()
+            .cmp(&b.display_name().to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    };
+    let known = |value: Option<f64>| value.filter(|v| *v > 0.0);
+    bodies.sort_by(|a, b| {
+        let by = |value: fn(&CelestialBody) -> Option<f64>| {
+            compare_optional(known(value(a)), known(value(b)), descending)
+        };
+        match field {
+            SortField::Name if descending => by_name(b, a),
+            SortField::Name => by_name(a, b),
+            SortField::Mass => by(mass_magnitude),
+            SortField::Radius => by(|body| body.mean_radius),
+            SortField::Gravity => by(|body| body.gravity),
+            SortField::Distance => by(|body| body.semimajor_axis),
         }
+        .then_with(|| by_name(a, b))
+    });
+}
+
+// Function to find the `n` bodies whose mean radius is closest to the target's, nearest first.
+// The target itself and bodies without radius data are left out.
+fn closest_by_radius<'a>(
+    target: &CelestialBody,
+    pool: &'a [CelestialBody],
+    n: usize,
+) -> Vec<&'a CelestialBody> {
+    let Some(radius) = target.mean_radius.filter(|r| *r > 0.0) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<(&CelestialBody, f64)> = pool
+        .iter()
+        .filter(|body| body.id != target.id)
+        .filter_map(|body| {
+            let other = body.mean_radius.filter(|r| *r > 0.0)?;
+            Some((body, (other - radius).abs()))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+    candidates
+        .into_iter()
+        .take(n)
+        .map(|(body, _)| body)
+        .collect()
+}
+
+// Function to find every body whose English or native name contains the term, ignoring case.
+fn search_bodies<'a>(term: &str, bodies: &'a [CelestialBody]) -> Vec<&'a CelestialBody> {
+    let term = term.to_lowercase();
+    bodies
+        .iter()
+        .filter(|body| {
+            body.english_name.to_lowercase().contains(&term)
+                || body.name.to_lowercase().contains(&term)
+        })
+        .collect()
+}
+
+// Function to find the body a moon orbits by looking for the moon in each body's moon list,
+// matching the id at the end of the link or, failing that, the name. Returns None for bodies
+// that are nobody's moon, such as the Sun and the planets.
+fn find_parent<'a>(
+    moon: &CelestialBody,
+    planets: &'a [CelestialBody],
+) -> Option<&'a CelestialBody> {
+    planets
+        .iter()
+        .filter(|planet| planet.id != moon.id)
+        .find(|planet| {
+            planet.moons.iter().flatten().any(|entry| {
+                entry.rel.trim_end_matches('/').rsplit('/').next() == Some(moon.id.as_str())
+                    || entry.moon.eq_ignore_ascii_case(&moon.name)
+                    || entry.moon.eq_ignore_ascii_case(&moon.english_name)
+            })
+        })
+}
+
+// Function to make a value safe for a delimited row: CSV quotes fields containing a separator,
+// quote or newline, while TSV has no quoting and replaces tabs and newlines with spaces.
+fn delimited_field(value: &str, sep: char) -> String {
+    if sep == '\t' {
+        value.replace(['\t', '\n', '\r'], " ")
+    } else if value.contains(sep) || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Function to print a list of celestial bodies as delimited rows (CSV or TSV) under a header.
+fn print_bodies_delimited(
+    out: &mut dyn Write,
+    bodies: &[CelestialBody],
+    sep: char,
+) -> std::io::Result<()> {
+    let header = ["name", "id", "english_name", "is_planet"];
+    writeln!(out, "{}", header.join(&sep.to_string()))?;
+    for body in bodies {
+        let row = [
+            delimited_field(&body.name, sep),
+            delimited_field(&body.id, sep),
+            delimited_field(&body.english_name, sep),
+            body.is_planet.to_string(),
+        ];
+        writeln!(out, "{}", row.join(&sep.to_string()))?;
+    }
+    Ok(())
+}
+
+// Columns accepted by --columns for --format table, with their headings.
+const TABLE_COLUMNS: [(&str, &str); 9] = [
+    ("name", "Name"),
+    ("id", "ID"),
+    ("english_name", "English Name"),
+    ("type", "Type"),
+    ("is_planet", "Planet"),
+    ("mass", "Mass (kg)"),
+    ("gravity", "Gravity (m/s²)"),
+    ("radius", "Radius (km)"),
+    ("moons", "Moons"),
+];
+
+// Function to check --columns names, listing the valid ones when any are unknown.
+fn validate_columns(columns: &str) -> Result<(), String> {
+    let known = |column: &str| TABLE_COLUMNS.iter().any(|(name, _)| *name == column);
+    match columns
+        .split(',')
+        .map(str::trim)
+        .find(|column| !known(column))
+    {
+        Some(unknown) => Err(format!(
+            "Unknown column '{}'. Valid columns are: {}",
+            unknown,
+            TABLE_COLUMNS.map(|(name, _)| name).join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+// Function to render one cell of the listing table; missing values are left blank.
+fn table_cell(body: &CelestialBody, column: &str) -> String {
+    let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+    match column {
+        "name" => body.display_name().to_string(),
+        "id" => body.id.clone(),
+        "english_name" => body.english_name.clone(),
+        "type" => body.body_type.clone().unwrap_or_default(),
+        "is_planet" => body.is_planet.to_string(),
+        "mass" => mass_magnitude(body)
+            .map(|kg| format!("{:.3e}", kg))
+            .unwrap_or_default(),
+        "gravity" => number(body.gravity),
+        "radius" => number(body.mean_radius),
+        "moons" => body.moons.as_ref().map_or(0, Vec::len).to_string(),
+        _ => String::new(),
+    }
+}
+
+// Function to render an aligned plain-text table, each column as wide as its widest cell.
+fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = *width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut table = line(headers);
+    table.push_str(&line(&rule.iter().map(String::as_str).collect::<Vec<_>>()));
+    for row in rows {
+        table.push_str(&line(&row.iter().map(String::as_str).collect::<Vec<_>>()));
+    }
+    table
+}
+
+// Function to print a list of celestial bodies as an aligned table of the chosen columns.
+fn print_bodies_table(
+    out: &mut dyn Write,
+    bodies: &[CelestialBody],
+    columns: &[&str],
+) -> std::io::Result<()> {
+    let headers: Vec<&str> = columns
+        .iter()
+        .filter_map(|column| TABLE_COLUMNS.iter().find(|(name, _)| name == column))
+        .map(|(_, header)| *header)
+        .collect();
+    let rows: Vec<Vec<String>> = bodies
+        .iter()
+        .map(|body| {
+            columns
+                .iter()
+                .map(|column| table_cell(body, column))
+                .collect()
+        })
+        .collect();
+    write!(out, "{}", render_table(&headers, &rows))
+}
+
+// Function to escape pipe characters so a value can sit inside a Markdown table cell.
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+// Function to print a list of celestial bodies as a Markdown table.
+fn print_bodies_markdown(out: &mut dyn Write, bodies: &[CelestialBody]) -> std::io::Result<()> {
+    writeln!(out, "| Name | English Name | Type | Is Planet |")?;
+    writeln!(out, "| --- | --- | --- | --- |")?;
+    for body in bodies {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            markdown_cell(body.display_name()),
+            markdown_cell(&body.english_name),
+            markdown_cell(body.body_type.as_deref().unwrap_or_default()),
+            body.is_planet
+        )?;
+    }
+    Ok(())
+}
+
+// Function to check that an API base URL is an absolute http(s) URL.
+fn validate_base_url(value: &str) -> Result<(), String> {
+    match reqwest::Url::parse(value) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!("unsupported URL scheme '{}'", url.scheme())),
+        Err(e) => Err(format!("'{}' is not a valid URL: {}", value, e)),
+    }
+}
+
+// Function to serialize a value for --format json: indented by default, on one line with
+// --compact.
+fn to_json<T: Serialize>(value: &T, matches: &ArgMatches) -> serde_json::Result<String> {
+    if matches.is_present("compact") {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
+
+// Function to turn a camelCase API key back into the snake_case name of the struct field.
+fn snake_case(key: &str) -> String {
+    let mut name = String::new();
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+// Function to append the gron assignments of a JSON value, and of everything inside it, under
+// the given path.
+fn gron_lines(path: &str, value: &serde_json::Value, lines: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            lines.push(format!("{} = {{}};", path));
+            for (key, field) in fields {
+                gron_lines(&format!("{}.{}", path, snake_case(key)), field, lines);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            lines.push(format!("{} = [];", path));
+            for (index, item) in items.iter().enumerate() {
+                gron_lines(&format!("{}[{}]", path, index), item, lines);
+            }
+        }
+        scalar => lines.push(format!("{} = {};", path, scalar)),
+    }
+}
+
+// Function to flatten a body into gron-style assignment lines such as `body.gravity = 3.71;`,
+// one per field, so nested data can be grepped.
+fn to_gron(body: &CelestialBody) -> String {
+    let mut lines = Vec::new();
+    gron_lines(
+        "body",
+        &serde_json::to_value(body).unwrap_or_default(),
+        &mut lines,
+    );
+    lines.join("\n")
+}
+
+// Function to flatten a list of bodies the same way, indexing each one under `bodies`.
+fn to_gron_list(bodies: &[CelestialBody]) -> String {
+    let mut lines = Vec::new();
+    gron_lines(
+        "bodies",
+        &serde_json::to_value(bodies).unwrap_or_default(),
+        &mut lines,
+    );
+    lines.join("\n")
+}
+
+// Function to escape text for use inside an XML element.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Function to append a JSON value as an XML element named `tag`, indented by `depth` levels.
+// Absent (null) values are left out; array items are named after the array, singularised.
+fn xml_element(tag: &str, value: &serde_json::Value, depth: usize, xml: &mut String) {
+    let indent = "  ".repeat(depth);
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Object(fields) => {
+            xml.push_str(&format!("{}<{}>\n", indent, tag));
+            for (key, field) in fields {
+                xml_element(key, field, depth + 1, xml);
+            }
+            xml.push_str(&format!("{}</{}>\n", indent, tag));
+        }
+        serde_json::Value::Array(items) => {
+            let item_tag = match tag.strip_suffix("ies") {
+                Some(stem) => format!("{}y", stem),
+                None => tag.strip_suffix('s').unwrap_or("item").to_string(),
+            };
+            xml.push_str(&format!("{}<{}>\n", indent, tag));
+            for item in items {
+                xml_element(&item_tag, item, depth + 1, xml);
+            }
+            xml.push_str(&format!("{}</{}>\n", indent, tag));
+        }
+        serde_json::Value::String(text) => {
+            xml.push_str(&format!(
+                "{}<{}>{}</{}>\n",
+                indent,
+                tag,
+                xml_escape(text),
+                tag
+            ));
+        }
+        scalar => xml.push_str(&format!("{}<{}>{}</{}>\n", indent, tag, scalar, tag)),
+    }
+}
+
+// Function to serialize a body as an XML document with a <celestialBody> root, or a list of
+// bodies under <celestialBodies>.
+fn to_xml<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    let value = serde_json::to_value(value)?;
+    let root = if value.is_array() {
+        "celestialBodies"
+    } else {
+        "celestialBody"
+    };
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml_element(root, &value, 0, &mut xml);
+    Ok(xml)
+}
+
+// Function to write a rendered result to the --output file, creating its parent directory if
+// needed, or to stdout when no file was given.
+fn write_output(path: Option<&str>, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = match path {
+        Some(path) => Path::new(path),
+        None => return Ok(std::io::stdout().write_all(contents)?),
+    };
+    let describe = |e: std::io::Error| format!("Could not write to '{}': {}", path.display(), e);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(describe)?;
+    }
+    fs::write(path, contents).map_err(describe)?;
+    Ok(())
+}
+
+// Function to show output through $PAGER (or `less -R`), returning false when no pager could
+// be started so the caller can print directly instead.
+fn page_output(contents: &[u8]) -> bool {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+    let Ok(mut child) = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe; the rest of the output is simply unwanted.
+        let _ = stdin.write_all(contents);
+    }
+    let _ = child.wait();
+    true
+}
+
+// Function to build the --watch argument accepted by the listing and details commands.
+fn watch_arg<'a>() -> Arg<'a> {
+    Arg::with_name("watch")
+        .long("watch")
+        .value_name("SECONDS")
+        .help("Refresh the output every SECONDS seconds until interrupted")
+        .takes_value(true)
+        .validator(validators::interval)
+}
+
+// Function to build the --jobs argument accepted by the commands that fetch several bodies.
+fn jobs_arg<'a>() -> Arg<'a> {
+    Arg::with_name("jobs")
+        .long("jobs")
+        .short('j')
+        .value_name("N")
+        .help("Fetch up to N bodies at the same time")
+        .takes_value(true)
+        .default_value("4")
+        .validator(validators::jobs)
+}
+
+// Function to build the --output argument accepted by the listing and details commands.
+fn output_arg<'a>() -> Arg<'a> {
+    Arg::with_name("output")
+        .long("output")
+        .short('o')
+        .value_name("PATH")
+        .help("Write the result to this file instead of stdout")
+        .takes_value(true)
+}
+
+// Function to build the arguments shared by the top-level listing and the `list` subcommand.
+fn listing_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::with_name("planets-only")
+            .long("planets-only")
+            .help("Only list bodies that are planets"),
+        Arg::with_name("unique")
+            .long("unique")
+            .help("Drop bodies with a blank name or an id already listed"),
+        Arg::with_name("type")
+            .long("type")
+            .value_name("BODY_TYPE")
+            .help("Only list bodies of this type, e.g. \"Dwarf Planet\"")
+            .takes_value(true),
+        Arg::with_name("min-mass")
+            .long("min-mass")
+            .value_name("KG")
+            .help("Only list bodies at least this massive, e.g. 1e22")
+            .takes_value(true)
+            .validator(validators::non_negative),
+        Arg::with_name("max-mass")
+            .long("max-mass")
+            .value_name("KG")
+            .help("Only list bodies at most this massive")
+            .takes_value(true)
+            .validator(validators::non_negative),
+        Arg::with_name("where")
+            .long("where")
+            .value_name("EXPR")
+            .help("Only list bodies matching an expression such as gravity>5 or is_planet=true (may be repeated)")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .validator(|value| predicate::parse_predicate(value).map(|_| ())),
+        Arg::with_name("only-with-field")
+            .long("only-with-field")
+            .value_name("FIELD")
+            .help("Only list bodies for which the API provides this field (may be repeated)")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .validator(|value| validate_fields(&[value])),
+        Arg::with_name("symbols")
+            .long("symbols")
+            .help("Prefix each body in the text listing with its astronomical symbol"),
+        Arg::with_name("summary")
+            .long("summary")
+            .help("Print how many bodies there are of each type instead of the bodies"),
+        Arg::with_name("sort-by")
+            .long("sort-by")
+            .help("Sort the listing by the given field")
+            .takes_value(true)
+            .possible_values(SORT_FIELDS)
+            .default_value("name"),
+        Arg::with_name("desc")
+            .long("desc")
+            .alias("reverse")
+            .help("Sort in descending order (same as --sort-dir desc)"),
+        Arg::with_name("sort-dir")
+            .long("sort-dir")
+            .help("Direction to sort in; bodies missing the sort field always come last")
+            .takes_value(true)
+            .possible_values(["asc", "desc"])
+            .conflicts_with("desc"),
+        Arg::with_name("limit")
+            .long("limit")
+            .value_name("N")
+            .help("Only print the first N bodies after filtering and sorting")
+            .takes_value(true)
+            .validator(validators::count),
+        Arg::with_name("columns")
+            .long("columns")
+            .value_name("COLUMNS")
+            .help("Comma-separated columns for --format table, e.g. name,type,gravity,radius")
+            .takes_value(true)
+            .default_value("name,type,gravity,radius")
+            .validator(validate_columns),
+        Arg::with_name("pager")
+            .long("pager")
+            .help("Show the listing through $PAGER (or less) when writing to a terminal"),
+        watch_arg(),
+        output_arg(),
+    ]
+}
+
+// Function to combine the API's mass value and exponent into a single comparable number in kilograms.
+fn mass_magnitude(body: &CelestialBody) -> Option<f64> {
+    let mass = body.mass.as_ref()?;
+    Some(mass.mass_value? * 10f64.powi(mass.mass_exponent?))
+}
+
+// Function to list the numeric properties of a body as (label, value) pairs.
+fn numeric_fields(body: &CelestialBody) -> Vec<(&'static str, Option<f64>)> {
+    vec![
+        ("Mass (kg)", mass_magnitude(body)),
+        ("Density (g/cm³)", body.density),
+        ("Gravity (m/s²)", body.gravity),
+        ("Escape Velocity (m/s)", body.escape),
+        ("Mean Radius (km)", body.mean_radius),
+        ("Equatorial Radius (km)", body.equa_radius),
+        ("Polar Radius (km)", body.polar_radius),
+        ("Flattening", body.flattening),
+        ("Orbital Period (days)", body.sideral_orbit),
+        ("Rotation Period (hours)", body.sideral_rotation),
+        ("Axial Tilt (degrees)", body.axial_tilt),
+        ("Average Temperature (K)", body.avg_temp.map(f64::from)),
+    ]
+}
+
+// Function to format a float rounded to `precision` decimal places, dropping trailing zeros so
+// that whole and short values stay as the API gave them.
+fn fmt_float(value: f64, precision: usize) -> String {
+    let text = format!("{:.*}", precision, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    // Rounding a tiny negative number would otherwise print as "-0".
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+// Function to group the integer digits of a number in threes, e.g. 69911 as "69,911". When
+// `sep` is '.' the decimal mark becomes ',' so the two cannot be confused.
+fn group_digits(n: f64, sep: char) -> String {
+    if !n.is_finite() {
+        return n.to_string();
+    }
+    let text = n.abs().to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let mut grouped = String::new();
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push(sep);
+        }
+        grouped.push(digit);
+    }
+    if !fraction.is_empty() {
+        grouped.push(if sep == '.' { ',' } else { '.' });
+        grouped.push_str(fraction);
+    }
+    if n < 0.0 {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
+
+// Function to format a float for the text output: rounded as by fmt_float, then grouped when
+// a digit separator was asked for.
+fn fmt_number(value: f64, precision: usize, separator: Option<char>) -> String {
+    let text = fmt_float(value, precision);
+    match (separator, text.parse::<f64>()) {
+        (Some(sep), Ok(rounded)) => group_digits(rounded, sep),
+        _ => text,
+    }
+}
+
+// Function to work out the thousands separator from --locale, or ',' for a bare --group-digits.
+fn digit_separator(matches: &ArgMatches) -> Option<char> {
+    match matches.value_of("locale") {
+        Some("en") => Some(','),
+        Some(_) => Some('.'),
+        None if matches.is_present("group-digits") => Some(','),
+        None => None,
+    }
+}
+
+// Function to style a field label; plain text when color output is disabled.
+fn label(text: &str) -> ColoredString {
+    text.cyan().bold()
+}
+
+// Function to pick a color for an average temperature: blue when very cold, green when
+// temperate, yellow when warm and red when very hot.
+fn temp_color(kelvin: i32) -> Color {
+    match kelvin {
+        ..=200 => Color::Blue,
+        201..=320 => Color::Green,
+        321..=500 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+// Function to print one labelled line of the details view.
+fn print_field(
+    out: &mut dyn Write,
+    name: &str,
+    value: impl std::fmt::Display,
+) -> std::io::Result<()> {
+    writeln!(out, "{}: {}", label(name), value)
+}
+
+// Names accepted by --fields, in the order the details view prints them.
+const FIELD_NAMES: [&str; 25] = [
+    "name",
+    "id",
+    "english_name",
+    "is_planet",
+    "mass",
+    "density",
+    "gravity",
+    "escape",
+    "mean_radius",
+    "equa_radius",
+    "polar_radius",
+    "volume",
+    "surface_area",
+    "flattening",
+    "semimajor_axis",
+    "perihelion",
+    "aphelion",
+    "eccentricity",
+    "sunlight",
+    "sideral_orbit",
+    "sideral_rotation",
+    "axial_tilt",
+    "avg_temp",
+    "body_type",
+    "discovered_by",
+];
+
+// Struct to hold one rendered field of the details view, keeping the raw value apart from its
+// unit so that --quiet can print the bare value.
+struct FieldValue {
+    label: &'static str,
+    value: String,
+    unit: String,
+}
+
+impl FieldValue {
+    // Function to build a field whose value is printed as "<value> <unit>".
+    fn new(label: &'static str, value: impl ToString, unit: &str) -> FieldValue {
+        let unit = if unit.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", unit)
+        };
+        FieldValue {
+            label,
+            value: value.to_string(),
+            unit,
+        }
+    }
+}
+
+// Function to express a value as a multiple of Earth's, noting when the body has no data.
+fn relative_to_earth(label: &'static str, value: Option<f64>, earth_value: f64) -> FieldValue {
+    let (value, unit) = match value {
+        Some(value) => (format!("{:.2}", value / earth_value), "× Earth"),
+        None => ("N/A".to_string(), " (no data to compare with Earth)"),
+    };
+    FieldValue {
+        label,
+        value,
+        unit: unit.to_string(),
+    }
+}
+
+// Function to express an escape velocity in m/s as a multiple of Earth's.
+fn escape_relative_to_earth(escape_mps: f64) -> f64 {
+    escape_mps / EARTH.escape
+}
+
+// Function to describe in words how hard it is to leave a body compared with leaving Earth.
+fn escape_note(escape_mps: f64) -> String {
+    let ratio = escape_relative_to_earth(escape_mps);
+    if ratio < 0.9 {
+        format!("{:.1}× easier to leave than Earth", 1.0 / ratio)
+    } else if ratio <= 1.1 {
+        "about as hard to leave as Earth".to_string()
+    } else if ratio < 3.0 {
+        format!("{:.1}× harder to leave than Earth", ratio)
+    } else {
+        "much harder to leave than Earth".to_string()
+    }
+}
+
+// Function to check whether a body spins backwards, which the API reports as a negative
+// sidereal rotation period.
+fn is_retrograde(body: &CelestialBody) -> bool {
+    body.sideral_rotation.is_some_and(|hours| hours < 0.0)
+}
+
+// Function to look up a field by its --fields name, returning its label, value and unit with
+// the selected units applied. Returns None for unknown fields and for derived fields whose
+// inputs are missing.
+fn field_value(body: &CelestialBody, field: &str, options: &DisplayOptions) -> Option<FieldValue> {
+    let relative = options.relative_to_earth;
+    let imperial = options.units == UnitSystem::Imperial;
+    let to_length = |km: f64| if imperial { units::km_to_miles(km) } else { km };
+    let length_unit = if imperial { "mi" } else { "km" };
+    let float = |value: f64| fmt_number(value, options.precision, options.digit_separator);
+    let length = |label, km: Option<f64>| {
+        FieldValue::new(label, float(to_length(km.unwrap_or(0.0))), length_unit)
+    };
+    let value = match field {
+        "name" => FieldValue::new("Name", &body.name, ""),
+        "id" => FieldValue::new("ID", &body.id, ""),
+        "english_name" => FieldValue::new("English Name", &body.english_name, ""),
+        "is_planet" => FieldValue::new("Is Planet", body.is_planet, ""),
+        "mass" if relative => relative_to_earth("Mass", mass_magnitude(body), EARTH.mass_kg),
+        "mass" => match body.mass.as_ref().map(|m| (m.mass_value, m.mass_exponent)) {
+            Some((Some(value), Some(exponent))) => {
+                FieldValue::new("Mass", format!("{}e{}", value, exponent), "")
+            }
+            _ => FieldValue::new("Mass", "Not available", ""),
+        },
+        "density" => match body.density.filter(|density| *density > 0.0) {
+            Some(density) => FieldValue::new(
+                "Density",
+                float(density),
+                &format!("g/cm³ (likely {})", physics::composition_hint(density)),
+            ),
+            None => FieldValue::new("Density", 0.0, "g/cm³"),
+        },
+        "gravity" if relative => relative_to_earth("Gravity", body.gravity, EARTH.gravity),
+        "gravity" if imperial => FieldValue::new(
+            "Gravity",
+            float(units::mps2_to_fps2(body.gravity.unwrap_or(0.0))),
+            "ft/s²",
+        ),
+        "gravity" => FieldValue::new("Gravity", float(body.gravity.unwrap_or(0.0)), "m/s²"),
+        "escape" if imperial => match body.escape.filter(|escape| *escape > 0.0) {
+            Some(escape) => FieldValue::new(
+                "Escape Velocity",
+                float(units::mps_to_mph(escape)),
+                &format!("mph ({})", escape_note(escape)),
+            ),
+            None => FieldValue::new("Escape Velocity", 0.0, "mph"),
+        },
+        "escape" => match body.escape.filter(|escape| *escape > 0.0) {
+            Some(escape) => FieldValue::new(
+                "Escape Velocity",
+                float(escape),
+                &format!("m/s ({:.2} km/s, {})", escape / 1000.0, escape_note(escape)),
+            ),
+            None => FieldValue::new("Escape Velocity", 0.0, "m/s"),
+        },
+        "mean_radius" if relative => {
+            relative_to_earth("Mean Radius", body.mean_radius, EARTH.mean_radius_km)
+        }
+        "mean_radius" => length("Mean Radius", body.mean_radius),
+        "equa_radius" => length("Equatorial Radius", body.equa_radius),
+        "polar_radius" => length("Polar Radius", body.polar_radius),
+        "volume" => {
+            let volume = physics::sphere_volume(to_length(body.mean_radius?));
+            FieldValue::new(
+                "Volume",
+                format!("{:.3e}", volume),
+                &format!("{}³", length_unit),
+            )
+        }
+        "surface_area" => {
+            let area = physics::sphere_surface_area(to_length(body.mean_radius?));
+            FieldValue::new(
+                "Surface Area",
+                format!("{:.3e}", area),
+                &format!("{}²", length_unit),
+            )
+        }
+        "flattening" => match body.flattening.filter(|flattening| *flattening > 0.0) {
+            Some(flattening) => FieldValue::new(
+                "Flattening",
+                float(flattening),
+                &format!("({})", physics::oblateness_description(flattening)),
+            ),
+            None => FieldValue::new("Flattening", 0.0, ""),
+        },
+        "semimajor_axis" => match body.semimajor_axis.filter(|km| *km > 0.0) {
+            Some(km) => FieldValue::new(
+                "Semi-major Axis",
+                float(to_length(km)),
+                &format!("{} ({:.3} AU)", length_unit, units::km_to_au(km)),
+            ),
+            None => length("Semi-major Axis", body.semimajor_axis),
+        },
+        "perihelion" | "aphelion" | "eccentricity" => {
+            let (perihelion, aphelion) = apsides(body)?;
+            let apsis = |label, km: f64| {
+                FieldValue::new(
+                    label,
+                    float(to_length(km)),
+                    &format!("{} ({:.3} AU)", length_unit, units::km_to_au(km)),
+                )
+            };
+            match field {
+                "perihelion" => apsis("Perihelion", perihelion),
+                "aphelion" => apsis("Aphelion", aphelion),
+                _ => FieldValue::new(
+                    "Eccentricity",
+                    float(physics::eccentricity_from_apsides(aphelion, perihelion)),
+                    "",
+                ),
+            }
+        }
+        "sunlight" => {
+            let km = solar_distance(body)?;
+            let flux = physics::solar_flux_relative(units::km_to_au(km));
+            // The far outer system would round to zero, so faint sunlight is shown in
+            // scientific notation instead.
+            let value = if flux < 0.01 {
+                format!("{:.1e}", flux)
+            } else {
+                format!("{:.2}", flux)
+            };
+            FieldValue {
+                label: "Sunlight",
+                value,
+                unit: "× Earth".to_string(),
+            }
+        }
+        "sideral_orbit" if relative => relative_to_earth(
+            "Orbital Period",
+            body.sideral_orbit,
+            EARTH.sideral_orbit_days,
+        ),
+        "sideral_orbit" => {
+            let days = body.sideral_orbit.unwrap_or(0.0);
+            // Periods longer than a year also get their length in years, which reads better
+            // for the outer planets.
+            let unit = if days > units::DAYS_PER_YEAR {
+                format!("days ({:.1} years)", units::days_to_years(days))
+            } else {
+                "days".to_string()
+            };
+            FieldValue::new("Orbital Period", float(days), &unit)
+        }
+        // The API marks retrograde rotation with a negative period; the direction is noted
+        // separately by the details view.
+        "sideral_rotation" => {
+            let hours = body.sideral_rotation.unwrap_or(0.0).abs();
+            // Slow rotators also get their period in days, which is easier to picture.
+            let unit = if hours > 48.0 {
+                format!("hours ({:.1} Earth days)", units::hours_to_days(hours))
+            } else {
+                "hours".to_string()
+            };
+            FieldValue::new("Rotation Period", float(hours), &unit)
+        }
+        "axial_tilt" => FieldValue::new(
+            "Axial Tilt",
+            float(body.axial_tilt.unwrap_or(0.0)),
+            "degrees",
+        ),
+        "avg_temp" => {
+            let (temperature, unit) = options.temp_unit.convert(body.avg_temp.unwrap_or(0));
+            FieldValue::new("Average Temperature", float(temperature), unit)
+        }
+        "body_type" => FieldValue::new(
+            "Body Type",
+            body.body_type.as_deref().unwrap_or("Not specified"),
+            "",
+        ),
+        "discovered_by" => {
+            let by = body.discovered_by.as_deref().unwrap_or_default();
+            let date = body.discovery_date.as_deref().unwrap_or_default();
+            let discovery = match (by.is_empty(), date.is_empty()) {
+                (true, _) => "Unknown / ancient".to_string(),
+                (false, true) => by.to_string(),
+                (false, false) => format!("{} on {}", by, date),
+            };
+            FieldValue::new("Discovered by", discovery, "")
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+// Function to get a body's perihelion and aphelion in km. The apsides, and the eccentricity
+// derived from them, are only shown as a pair.
+fn apsides(body: &CelestialBody) -> Option<(f64, f64)> {
+    let perihelion = body.perihelion.filter(|km| *km > 0.0)?;
+    let aphelion = body.aphelion.filter(|km| *km > 0.0)?;
+    Some((perihelion, aphelion))
+}
+
+// Function to get the semi-major axis of a body orbiting the Sun, in km. A moon's semi-major
+// axis is measured around its planet, so moons have no distance to the Sun here.
+fn solar_distance(body: &CelestialBody) -> Option<f64> {
+    let is_moon = body
+        .body_type
+        .as_deref()
+        .is_some_and(|t| t.eq_ignore_ascii_case("moon"));
+    body.semimajor_axis.filter(|km| *km > 0.0 && !is_moon)
+}
+
+// Function to check whether the API record lacks the data a field is built from. The API
+// reports unknown measurements as 0 and unknown text as "", so those count as missing too.
+fn field_is_missing(body: &CelestialBody, field: &str) -> bool {
+    let unknown = |value: Option<f64>| !value.is_some_and(|v| v > 0.0);
+    let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+    match field {
+        "name" => body.name.trim().is_empty(),
+        "id" => body.id.trim().is_empty(),
+        "english_name" => body.english_name.trim().is_empty(),
+        "is_planet" => false,
+        "mass" => unknown(mass_magnitude(body)),
+        "density" => unknown(body.density),
+        "gravity" => unknown(body.gravity),
+        "escape" => unknown(body.escape),
+        "mean_radius" | "volume" | "surface_area" => unknown(body.mean_radius),
+        "equa_radius" => unknown(body.equa_radius),
+        "polar_radius" => unknown(body.polar_radius),
+        "flattening" => unknown(body.flattening),
+        "semimajor_axis" => unknown(body.semimajor_axis),
+        "perihelion" | "aphelion" | "eccentricity" => apsides(body).is_none(),
+        "sunlight" => solar_distance(body).is_none(),
+        "sideral_orbit" => unknown(body.sideral_orbit),
+        // Retrograde rotation is reported as a negative period, so only 0 means unknown.
+        "sideral_rotation" => body.sideral_rotation.is_none_or(|hours| hours == 0.0),
+        "axial_tilt" => unknown(body.axial_tilt),
+        "avg_temp" => unknown(body.avg_temp.map(f64::from)),
+        "body_type" => blank(&body.body_type),
+        // An empty discoverer is how the API marks bodies known since antiquity.
+        "discovered_by" => body.discovered_by.is_none(),
+        _ => false,
+    }
+}
+
+// Function to return the label a field is printed under, for fields that have no value to show.
+fn field_label(field: &str) -> &'static str {
+    match field {
+        "name" => "Name",
+        "id" => "ID",
+        "english_name" => "English Name",
+        "is_planet" => "Is Planet",
+        "mass" => "Mass",
+        "density" => "Density",
+        "gravity" => "Gravity",
+        "escape" => "Escape Velocity",
+        "mean_radius" => "Mean Radius",
+        "equa_radius" => "Equatorial Radius",
+        "polar_radius" => "Polar Radius",
+        "volume" => "Volume",
+        "surface_area" => "Surface Area",
+        "flattening" => "Flattening",
+        "semimajor_axis" => "Semi-major Axis",
+        "perihelion" => "Perihelion",
+        "aphelion" => "Aphelion",
+        "eccentricity" => "Eccentricity",
+        "sunlight" => "Sunlight",
+        "sideral_orbit" => "Orbital Period",
+        "sideral_rotation" => "Rotation Period",
+        "axial_tilt" => "Axial Tilt",
+        "avg_temp" => "Average Temperature",
+        "body_type" => "Body Type",
+        _ => "Discovered by",
+    }
+}
+
+// Function to check --fields names, listing the valid ones when any are unknown.
+fn validate_fields(fields: &[&str]) -> Result<(), String> {
+    match fields.iter().find(|field| !FIELD_NAMES.contains(field)) {
+        Some(unknown) => Err(format!(
+            "Unknown field '{}'. Valid fields are: {}",
+            unknown,
+            FIELD_NAMES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+// Function to print only the requested fields of a body, in the order given.
+// Under --quiet only the bare values are printed, one per line.
+fn print_selected_fields(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    fields: &[&str],
+    options: &DisplayOptions,
+) -> std::io::Result<()> {
+    for name in fields {
+        if options.strict && field_is_missing(body, name) {
+            if options.quiet {
+                writeln!(out, "MISSING")?;
+            } else {
+                print_field(out, field_label(name), "MISSING".red())?;
+            }
+        } else if let Some(field) = field_value(body, name, options) {
+            let text = format!("{}{}", field.value, field.unit);
+            let kelvin = body.avg_temp.filter(|k| *name == "avg_temp" && *k > 0);
+            if options.quiet {
+                writeln!(out, "{}", field.value)?;
+            } else if let Some(kelvin) = kelvin {
+                print_field(out, field.label, text.color(temp_color(kelvin)))?;
+            } else {
+                print_field(out, field.label, text)?;
+            }
+        } else if options.quiet {
+            // Keep one line per requested field so the values still line up with them.
+            writeln!(out, "N/A")?;
+        } else {
+            print_field(out, field_label(name), "N/A")?;
+        }
+    }
+    Ok(())
+}
+
+// Function to pick the astronomical symbol of a body by its English name, falling back to its
+// type for moons and a plain bullet for everything else.
+fn body_symbol(body: &CelestialBody) -> &'static str {
+    match body.english_name.to_lowercase().as_str() {
+        "sun" => "☉",
+        "mercury" => "☿",
+        "venus" => "♀",
+        "earth" => "⊕",
+        "mars" => "♂",
+        "jupiter" => "♃",
+        "saturn" => "♄",
+        "uranus" => "⛢",
+        "neptune" => "♆",
+        "pluto" => "♇",
+        _ if body.body_type.as_deref() == Some("Moon") => "🌙",
+        _ => "•",
+    }
+}
+
+// Function to print the one-line summary of a body used by the listing, optionally prefixed
+// with its symbol.
+fn print_body_summary(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    symbols: bool,
+) -> std::io::Result<()> {
+    if symbols {
+        write!(out, "{} ", body_symbol(body))?;
+    }
+    writeln!(
+        out,
+        "{}: {}, {}: {}, {}: {}",
+        "Name".green().bold(),
+        body.display_name(),
+        "ID".green().bold(),
+        body.id,
+        "Is Planet".green().bold(),
+        body.is_planet.to_string().blue()
+    )
+}
+
+// Function to print the full human-readable details of a celestial body.
+fn print_body_details(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    options: &DisplayOptions,
+) -> std::io::Result<()> {
+    if options.quiet {
+        return print_selected_fields(out, body, &FIELD_NAMES, options);
+    }
+    // The name not used as the title is still shown alongside it.
+    let (other_name_label, other_name) = if body.display_name() == body.english_name {
+        ("Native Name", &body.name)
+    } else {
+        ("English Name", &body.english_name)
+    };
+    writeln!(
+        out,
+        "{}: {}, {}: {}, {}: {}, {}: {}",
+        label("Name"),
+        body.display_name(),
+        label("ID"),
+        body.id,
+        label(other_name_label),
+        other_name,
+        label("Is Planet"),
+        body.is_planet
+    )?;
+    for field in &FIELD_NAMES[4..] {
+        // Fields that do not apply to the body, such as sunlight for a moon, are left out.
+        if !options.strict && field_value(body, field, options).is_none() {
+            continue;
+        }
+        if *field == "mass"
+            && !options.relative_to_earth
+            && !options.strict
+            && mass_magnitude(body).is_none()
+        {
+            let message = if body.mass.is_some() {
+                "Mass data is incomplete or not available."
+            } else {
+                "No mass data provided by the API."
+            };
+            writeln!(out, "{}", message.red())?;
+        } else {
+            print_selected_fields(out, body, &[field], options)?;
+        }
+        if *field == "sideral_rotation" && is_retrograde(body) {
+            print_field(out, "Rotation", "retrograde")?;
+        }
+    }
+    Ok(())
+}
+
+// Function to format one value of the comparison table, switching to scientific notation for
+// very large numbers.
+fn comparison_cell(value: Option<f64>, precision: usize, separator: Option<char>) -> String {
+    match value {
+        Some(v) if v.abs() >= 1e9 => format!("{:.3e}", v),
+        Some(v) => fmt_number(v, precision, separator),
+        None => "N/A".to_string(),
+    }
+}
+
+// Function to print an aligned table comparing every numeric field of several bodies, one
+// column per body sized to its widest value. With exactly two bodies a ratio column is added,
+// and with `highlight` each row's largest value is shown in green and its smallest in red.
+fn print_comparison(
+    bodies: &[CelestialBody],
+    highlight: bool,
+    precision: usize,
+    separator: Option<char>,
+) {
+    let rows: Vec<Vec<(&str, Option<f64>)>> = bodies.iter().map(numeric_fields).collect();
+    let labels: Vec<&str> = rows
+        .first()
+        .map(|row| row.iter().map(|(label, _)| *label).collect())
+        .unwrap_or_default();
+    let label_width = labels
+        .iter()
+        .chain(std::iter::once(&"Field"))
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let widths: Vec<usize> = bodies
+        .iter()
+        .zip(&rows)
+        .map(|(body, row)| {
+            row.iter()
+                .map(|(_, value)| {
+                    comparison_cell(*value, precision, separator)
+                        .chars()
+                        .count()
+                })
+                .chain(std::iter::once(body.display_name().chars().count()))
+                .max()
+                .unwrap_or(0)
+                + 2
+        })
+        .collect();
+    let with_ratio = bodies.len() == 2;
+
+    let mut header = format!("{:<width$}", "Field", width = label_width);
+    for (body, width) in bodies.iter().zip(&widths) {
+        header.push_str(&format!("{:>width$}", body.display_name(), width = *width));
+    }
+    if with_ratio {
+        header.push_str(&format!("{:>10}", "Ratio"));
+    }
+    println!("{}", header.green().bold());
+
+    for (index, label) in labels.iter().enumerate() {
+        let values: Vec<Option<f64>> = rows.iter().map(|row| row[index].1).collect();
+        let known = values.iter().flatten();
+        let max = known.clone().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min = known.copied().fold(f64::INFINITY, f64::min);
+        let mut line = format!("{:<width$}", label, width = label_width);
+        for (value, width) in values.iter().zip(&widths) {
+            // Pad before coloring so the escape codes do not count towards the width.
+            let cell = format!(
+                "{:>width$}",
+                comparison_cell(*value, precision, separator),
+                width = *width
+            );
+            let cell = match value {
+                Some(v) if highlight && max > min && *v == max => cell.green().to_string(),
+                Some(v) if highlight && max > min && *v == min => cell.red().to_string(),
+                Some(v) if *label == "Average Temperature (K)" && *v > 0.0 => {
+                    cell.color(temp_color(*v as i32)).to_string()
+                }
+                _ => cell,
+            };
+            line.push_str(&cell);
+        }
+        if with_ratio {
+            let ratio = match (values[0], values[1]) {
+                (Some(a), Some(b)) if b != 0.0 => format!("{:.2}×", a / b),
+                _ => String::new(),
+            };
+            line.push_str(&format!("{:>10}", ratio));
+        }
+        println!("{}", line);
+    }
+}
+
+// Function to print one horizontal bar per body, scaled so the largest mean radius spans the
+// full width. Bodies without a radius are skipped with a warning.
+fn render_scale_bars(bodies: &[CelestialBody]) {
+    const MAX_BAR_WIDTH: f64 = 60.0;
+    let mut measured = Vec::new();
+    for body in bodies {
+        match body.mean_radius {
+            Some(radius) if radius > 0.0 => measured.push((body, radius)),
+            _ => warn!("Skipping {}: it has no radius data", body.display_name()),
+        }
+    }
+    let largest = measured
+        .iter()
+        .map(|(_, radius)| *radius)
+        .fold(0.0, f64::max);
+    let name_width = measured
+        .iter()
+        .map(|(body, _)| body.display_name().chars().count())
+        .max()
+        .unwrap_or(0);
+    for (body, radius) in measured {
+        let width = ((radius / largest) * MAX_BAR_WIDTH).round().max(1.0) as usize;
+        println!(
+            "{:<name_width$} {} {} km",
+            body.display_name(),
+            "█".repeat(width),
+            radius
+        );
+    }
+}
+
+// Function to compute the Earth Similarity Index from mean radius, density, escape velocity and
+// surface temperature, using the standard weighted geometric mean of per-property similarities.
+// The API reports unknown temperatures as 0 K, so those count as missing.
+fn earth_similarity_index(body: &CelestialBody) -> Option<f64> {
+    let properties = [
+        (body.mean_radius?, EARTH.mean_radius_km, 0.57),
+        (body.density?, EARTH.density, 1.07),
+        (body.escape?, EARTH.escape, 0.70),
+        (
+            f64::from(body.avg_temp.filter(|kelvin| *kelvin > 0)?),
+            EARTH.avg_temp,
+            5.58,
+        ),
+    ];
+    let count = properties.len() as f64;
+    Some(
+        properties
+            .iter()
+            .map(|(value, earth, weight)| {
+                (1.0 - ((value - earth) / (value + earth)).abs()).powf(weight / count)
+            })
+            .product(),
+    )
+}
+
+// Function to describe an Earth Similarity Index score in words.
+fn similarity_band(esi: f64) -> &'static str {
+    if esi >= 0.8 {
+        "very Earth-like"
+    } else if esi >= 0.6 {
+        "somewhat Earth-like"
+    } else {
+        "not at all Earth-like"
+    }
+}
+
+// Function to print the Earth Similarity Index line of the details view.
+fn print_earth_similarity(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    quiet: bool,
+) -> std::io::Result<()> {
+    match earth_similarity_index(body) {
+        Some(esi) if quiet => writeln!(out, "{:.2}", esi),
+        Some(esi) => print_field(
+            out,
+            "Earth Similarity Index",
+            format!("{:.2} ({})", esi, similarity_band(esi)),
+        ),
+        None if quiet => writeln!(out, "N/A"),
+        None => print_field(
+            out,
+            "Earth Similarity Index",
+            "N/A (needs radius, density, escape velocity and temperature)",
+        ),
+    }
+}
+
+// Function to print the --hazard-check line of the details view. Only asteroids and comets
+// with a known perihelion are classified.
+fn print_hazard_check(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    quiet: bool,
+) -> std::io::Result<()> {
+    let body_type = body.body_type.as_deref().unwrap_or_default();
+    let small_body = physics::is_small_body(body_type);
+    let perihelion_au = body.perihelion.filter(|km| *km > 0.0).map(units::km_to_au);
+    let verdict = match perihelion_au {
+        Some(au) if small_body && physics::is_earth_crossing(au, body_type) => format!(
+            "yes (approximation: perihelion {:.3} AU is inside Earth's {} AU)",
+            au,
+            physics::EARTH_APHELION_AU
+        ),
+        Some(au) if small_body => format!(
+            "no (approximation: perihelion {:.3} AU is beyond Earth's {} AU)",
+            au,
+            physics::EARTH_APHELION_AU
+        ),
+        None if small_body => "N/A (no perihelion data)".to_string(),
+        _ => "N/A (only asteroids and comets are checked)".to_string(),
+    };
+    if quiet {
+        // The bare verdict is the first word: yes, no or N/A.
+        let word = verdict.split_whitespace().next().unwrap_or_default();
+        writeln!(out, "{}", word)
+    } else {
+        print_field(out, "Earth-orbit-crossing", verdict)
+    }
+}
+
+// Function to print the names of a planet's moons under a "Moons:" heading, stopping after
+// `limit` names and counting the rest.
+fn print_moon_names(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    limit: Option<usize>,
+) -> std::io::Result<()> {
+    let moons = body.moons.as_deref().unwrap_or_default();
+    if moons.is_empty() {
+        return writeln!(out, "{}: none known", label("Moons"));
+    }
+    writeln!(out, "{} ({}):", label("Moons"), moons.len())?;
+    let shown = limit.unwrap_or(moons.len()).min(moons.len());
+    for moon in &moons[..shown] {
+        writeln!(out, "  {}", moon.moon)?;
+    }
+    if shown < moons.len() {
+        writeln!(out, "  ... and {} more", moons.len() - shown)?;
+    }
+    Ok(())
+}
+
+// Function to print the parent planet line of the details view.
+fn print_parent(
+    out: &mut dyn Write,
+    body: &CelestialBody,
+    bodies: &[CelestialBody],
+    quiet: bool,
+) -> std::io::Result<()> {
+    match find_parent(body, bodies) {
+        Some(parent) if quiet => writeln!(out, "{}", parent.display_name()),
+        Some(parent) => print_field(out, "Parent Planet", parent.display_name()),
+        None if quiet => writeln!(out, "N/A"),
+        None => print_field(out, "Parent Planet", "N/A (does not orbit a planet)"),
+    }
+}
+
+// Function to handle the `details` subcommand.
+fn run_details(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // A lone "-" stands for the names on stdin, one per line, blank lines skipped.
+    let given: Vec<&str> = matches.values_of("name").into_iter().flatten().collect();
+    let from_stdin = given.contains(&"-");
+    let mut owned = Vec::new();
+    for name in &given {
+        if *name == "-" {
+            for line in std::io::stdin().lock().lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    owned.push(line.trim().to_string());
+                }
+            }
+        } else {
+            owned.push(name.to_string());
+        }
+    }
+    let names: Vec<&str> = owned.iter().map(String::as_str).collect();
+    let single = names.len() == 1 && !from_stdin;
+    let fields: Option<Vec<&str>> = matches
+        .value_of("fields")
+        .map(|fields| fields.split(',').map(str::trim).collect());
+    if let Some(fields) = &fields {
+        validate_fields(fields)?;
+    }
+
+    // A single name keeps the old behaviour of failing outright; with several, or with names
+    // read from stdin, each failure is reported and the remaining names are still fetched.
+    let mut bodies = Vec::new();
+    let mut failed = Vec::new();
+    let mut all_not_found = true;
+    let jobs = matches.value_of_t("jobs").unwrap_or(4);
+    for (name, result) in names.iter().zip(fetch_many(provider, &names, jobs)) {
+        match result {
+            Ok(body) => bodies.push(body),
+            Err(e) if single => return Err(e),
+            Err(e) => {
+                error!("{}", e);
+                all_not_found &= matches!(
+                    e.downcast_ref::<FetchError>(),
+                    Some(FetchError::NotFound { .. })
+                );
+                failed.push(name.to_string());
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    match matches.value_of("format") {
+        Some("json") if single => writeln!(out, "{}", to_json(&bodies[0], matches)?)?,
+        Some("json") => writeln!(out, "{}", to_json(&bodies, matches)?)?,
+        Some("jsonl") => {
+            for body in &bodies {
+                writeln!(out, "{}", serde_json::to_string(body)?)?;
+            }
+        }
+        Some("yaml") if single => write!(out, "{}", serde_yaml::to_string(&bodies[0])?)?,
+        Some("yaml") => write!(out, "{}", serde_yaml::to_string(&bodies)?)?,
+        Some("gron") if single => writeln!(out, "{}", to_gron(&bodies[0]))?,
+        Some("gron") => writeln!(out, "{}", to_gron_list(&bodies))?,
+        Some("xml") if single => write!(out, "{}", to_xml(&bodies[0])?)?,
+        Some("xml") => write!(out, "{}", to_xml(&bodies)?)?,
+        Some("csv") => print_bodies_delimited(&mut out, &bodies, ',')?,
+        Some("tsv") => print_bodies_delimited(&mut out, &bodies, '\t')?,
+        Some("markdown") => print_bodies_markdown(&mut out, &bodies)?,
+        _ => {
+            let units = match matches.value_of("units") {
+                Some("imperial") => UnitSystem::Imperial,
+                _ => UnitSystem::Metric,
+            };
+            // An explicit --temp-unit wins; otherwise follow the selected unit system.
+            let temp_unit = match matches.value_of("temp-unit") {
+                Some("celsius") => TemperatureUnit::Celsius,
+                Some("fahrenheit") => TemperatureUnit::Fahrenheit,
+                Some(_) => TemperatureUnit::Kelvin,
+                None if units == UnitSystem::Imperial => TemperatureUnit::Fahrenheit,
+                None => TemperatureUnit::Kelvin,
+            };
+            let options = DisplayOptions {
+                relative_to_earth: matches.is_present("relative-to-earth"),
+                units,
+                temp_unit,
+                quiet: matches.is_present("quiet"),
+                strict: matches.is_present("strict"),
+                precision: matches.value_of_t("precision").unwrap_or(3),
+                digit_separator: digit_separator(matches),
+            };
+            // The parent is found by scanning the moon lists of the whole catalogue.
+            let catalogue = if matches.is_present("show-parent") {
+                provider.get_all()?
+            } else {
+                Vec::new()
+            };
+            for (index, body) in bodies.iter().enumerate() {
+                if index > 0 {
+                    writeln!(out, "{}", "-".repeat(40))?;
+                }
+                match &fields {
+                    Some(fields) => print_selected_fields(&mut out, body, fields, &options)?,
+                    None => print_body_details(&mut out, body, &options)?,
+                }
+                if matches.is_present("earth-similarity") {
+                    print_earth_similarity(&mut out, body, options.quiet)?;
+                }
+                if matches.is_present("hazard-check") {
+                    print_hazard_check(&mut out, body, options.quiet)?;
+                }
+                if matches.is_present("show-parent") {
+                    print_parent(&mut out, body, &catalogue, options.quiet)?;
+                }
+                if matches.is_present("include-moons") && body.is_planet {
+                    let limit = matches.value_of_t("limit").ok();
+                    print_moon_names(&mut out, body, limit)?;
+                }
+            }
+        }
+    }
+    write_output(matches.value_of("output"), &out)?;
+
+    if !failed.is_empty() {
+        return Err(Box::new(PartialFailure {
+            failed,
+            all_not_found,
+        }));
+    }
+    // Strict mode checks the requested fields, or every field when --fields is not given,
+    // whatever the output format.
+    if matches.is_present("strict") {
+        let checked = fields.as_deref().unwrap_or(&FIELD_NAMES);
+        let incomplete: Vec<String> = bodies
+            .iter()
+            .filter_map(|body| {
+                let missing: Vec<&str> = checked
+                    .iter()
+                    .copied()
+                    .filter(|field| field_is_missing(body, field))
+                    .collect();
+                (!missing.is_empty())
+                    .then(|| format!("{}: {}", body.display_name(), missing.join(", ")))
+            })
+            .collect();
+        if !incomplete.is_empty() {
+            return Err(Box::new(MissingFields { bodies: incomplete }));
+        }
+    }
+    Ok(())
+}
+
+// Function to handle the `compare` subcommand.
+fn run_compare(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let names: Vec<&str> = matches.values_of("bodies").into_iter().flatten().collect();
+    let jobs = matches.value_of_t("jobs").unwrap_or(4);
+    let mut bodies = Vec::new();
+    let mut failed = Vec::new();
+    let mut all_not_found = true;
+    for (name, result) in names.iter().zip(fetch_many(provider, &names, jobs)) {
+        match result {
+            Ok(body) => bodies.push(body),
+            Err(e) => {
+                error!("{}", e);
+                all_not_found &= matches!(
+                    e.downcast_ref::<FetchError>(),
+                    Some(FetchError::NotFound { .. })
+                );
+                failed.push(name.to_string());
+            }
+        }
+    }
+    // Every body is needed for the table, so nothing is printed unless all of them arrived.
+    if !failed.is_empty() {
+        return Err(Box::new(PartialFailure {
+            failed,
+            all_not_found,
+        }));
+    }
+    // Numeric fields put the largest value in the first column; names go alphabetically.
+    if let Some(field) = matches.value_of("sort-columns-by") {
+        sort_bodies(&mut bodies, sort_field(field), field != "name");
+    }
+    print_comparison(
+        &bodies,
+        matches.is_present("highlight"),
+        matches.value_of_t("precision").unwrap_or(3),
+        digit_separator(matches),
+    );
+    Ok(())
+}
+
+// Function to handle the `moons` subcommand.
+fn run_moons(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = provider.get_one(matches.value_of("planet").unwrap_or_default())?;
+    let moons = body.moons.clone().unwrap_or_default();
+    if matches.is_present("count") {
+        println!("{}", moons.len());
+    } else if moons.is_empty() {
+        println!("{} has no known moons", body.display_name());
+    } else {
+        for moon in moons {
+            println!("{}", moon.moon);
+        }
+    }
+    Ok(())
+}
+
+// Function to handle the `parent` subcommand: the planet a moon orbits.
+fn run_parent(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let moon = provider.get_one(matches.value_of("moon").unwrap_or_default())?;
+    let bodies = provider.get_all()?;
+    match find_parent(&moon, &bodies) {
+        Some(parent) if matches.is_present("quiet") => println!("{}", parent.display_name()),
+        Some(parent) => println!("{} orbits {}", moon.display_name(), parent.display_name()),
+        None => println!("{} does not orbit a planet", moon.display_name()),
+    }
+    Ok(())
+}
+
+// Function to handle the `weight` subcommand: the force a mass would exert on the body's
+// surface, and the mass that would weigh the same on Earth.
+fn run_weight(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mass_kg: f64 = matches.value_of_t("mass-kg")?;
+    let body = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let gravity = body
+        .gravity
+        .filter(|gravity| *gravity > 0.0)
+        .ok_or_else(|| format!("No gravity data available for {}", body.display_name()))?;
+    let newtons = physics::weight_on_body(mass_kg, gravity);
+    let earth_equivalent = newtons / EARTH.gravity;
+    if matches.is_present("quiet") {
+        println!("{:.1}", earth_equivalent);
+    } else {
+        println!(
+            "A {} kg mass weighs {:.1} N on {}; you'd weigh {:.1} kg-equivalent there.",
+            mass_kg,
+            newtons,
+            body.display_name(),
+            earth_equivalent
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `jump` subcommand: how high a jump on Earth would reach on a body.
+fn run_jump(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let earth_jump_cm: f64 = matches.value_of_t("earth-jump-cm")?;
+    let body = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let gravity = body
+        .gravity
+        .filter(|gravity| *gravity > 0.0)
+        .ok_or_else(|| format!("No gravity data available for {}", body.display_name()))?;
+    let height = physics::jump_height(earth_jump_cm, gravity, EARTH.gravity);
+    if matches.is_present("quiet") {
+        println!("{:.0}", height);
+    } else {
+        println!(
+            "A {} cm Earth jump reaches {:.0} cm on {}",
+            earth_jump_cm,
+            height,
+            body.display_name()
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `age` subcommand: how many of a body's years an Earth age amounts to.
+fn run_age(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let earth_years: f64 = matches.value_of_t("earth-years")?;
+    let body = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let orbit_days = body
+        .sideral_orbit
+        .filter(|days| *days > 0.0)
+        .ok_or_else(|| format!("No orbital period available for {}", body.display_name()))?;
+    let age = physics::age_on_body(earth_years, orbit_days);
+    if matches.is_present("quiet") {
+        println!("{:.2}", age);
+    } else {
+        println!(
+            "{} Earth years = {:.2} years on {}",
+            earth_years,
+            age,
+            body.display_name()
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `random` subcommand: print the details of one body picked at random,
+// using --seed to make the pick reproducible.
+fn run_random(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bodies = provider.get_all()?;
+    if matches.is_present("planets-only") {
+        bodies = filter_planets(bodies);
+    }
+    if bodies.is_empty() {
+        return Err("No bodies to choose from".into());
+    }
+    let mut rng = match matches.value_of_t::<u64>("seed") {
+        Ok(seed) => StdRng::seed_from_u64(seed),
+        Err(_) => StdRng::from_entropy(),
+    };
+    let body = &bodies[rng.gen_range(0..bodies.len())];
+    let options = DisplayOptions {
+        quiet: matches.is_present("quiet"),
+        precision: matches.value_of_t("precision").unwrap_or(3),
+        digit_separator: digit_separator(matches),
+        ..DisplayOptions::default()
+    };
+    print_body_details(&mut std::io::stdout(), body, &options)?;
+    Ok(())
+}
+
+// Function to describe a planet in one paragraph for the tour.
+fn tour_paragraph(body: &CelestialBody) -> String {
+    let mut paragraph = format!("{}", body.display_name().bold());
+    match body.semimajor_axis.filter(|km| *km > 0.0) {
+        Some(km) => paragraph.push_str(&format!(
+            " orbits {:.2} AU from the Sun",
+            units::km_to_au(km)
+        )),
+        None => paragraph.push_str(" has no known orbit"),
+    }
+    if let Some(days) = body.sideral_orbit.filter(|days| *days > 0.0) {
+        paragraph.push_str(&format!(
+            ", taking {:.1} Earth years per lap",
+            units::days_to_years(days)
+        ));
+    }
+    paragraph.push('.');
+    if let (Some(radius), Some(gravity)) = (body.mean_radius, body.gravity) {
+        paragraph.push_str(&format!(
+            " It is {:.0} km in radius with a surface gravity of {} m/s².",
+            radius, gravity
+        ));
+    }
+    let moons = body.moons.as_ref().map_or(0, Vec::len);
+    paragraph.push_str(&match moons {
+        0 => " It has no known moons.".to_string(),
+        1 => " It has 1 known moon.".to_string(),
+        n => format!(" It has {} known moons.", n),
+    });
+    paragraph
+}
+
+// Function to handle the `tour` subcommand: the planets in orbital order, one paragraph each,
+// optionally pausing between them.
+fn run_tour(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut planets = filter_planets(provider.get_all()?);
+    sort_bodies(&mut planets, SortField::Distance, false);
+    let delay = Duration::from_millis(matches.value_of_t("delay").unwrap_or(0));
+    for (index, planet) in planets.iter().enumerate() {
+        if index > 0 {
+            std::thread::sleep(delay);
+            println!();
+        }
+        println!("{}", tour_paragraph(planet));
+    }
+    Ok(())
+}
+
+// Function to handle the `schema` subcommand: print the JSON Schema of one body as emitted by
+// `--format json`.
+fn run_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = schema_for!(CelestialBody);
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+// Function to rank planets by how many moons they have, most first and ties in name order.
+// Planets without a moons list count as having none.
+fn rank_by_moons(bodies: &[CelestialBody]) -> Vec<(&str, usize)> {
+    let mut ranking: Vec<(&str, usize)> = bodies
+        .iter()
+        .filter(|body| body.is_planet)
+        .map(|body| {
+            let moons = body.moons.as_ref().map_or(0, Vec::len);
+            (body.display_name(), moons)
+        })
+        .collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranking
+}
+
+// Function to handle the `leaderboard` subcommand. Moons are the only metric so far, so the
+// metric argument needs no dispatch yet.
+fn run_leaderboard(provider: &dyn BodyProvider) -> Result<(), Box<dyn std::error::Error>> {
+    let bodies = provider.get_all()?;
+    let ranking = rank_by_moons(&bodies);
+    let width = ranking
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(std::iter::once("Planet".len()))
+        .max()
+        .unwrap_or(0);
+    let header = format!(
+        "{:>4}  {:<width$}  {:>5}",
+        "Rank",
+        "Planet",
+        "Moons",
+        width = width
+    );
+    println!("{}", header.green().bold());
+    for (rank, (name, moons)) in ranking.iter().enumerate() {
+        println!(
+            "{:>4}  {:<width$}  {:>5}",
+            rank + 1,
+            name,
+            moons,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `orbit` subcommand: the speed and period of a circular orbit at some
+// altitude above the body's mean radius.
+fn run_orbit(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let altitude_km: f64 = matches.value_of_t("altitude-km")?;
+    let body = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let mass_kg = mass_magnitude(&body)
+        .ok_or_else(|| format!("No mass data available for {}", body.display_name()))?;
+    let radius_km = body
+        .mean_radius
+        .filter(|radius| *radius > 0.0)
+        .ok_or_else(|| format!("No radius data available for {}", body.display_name()))?;
+    let orbit_radius_m = (radius_km + altitude_km) * 1000.0;
+    let velocity = physics::orbital_velocity(mass_kg, orbit_radius_m);
+    let period_minutes = physics::orbital_period(orbit_radius_m, velocity) / 60.0;
+    if matches.is_present("quiet") {
+        println!("{:.1}", velocity);
+        println!("{:.1}", period_minutes);
+    } else {
+        println!(
+            "Circular orbit {} km above {}:",
+            altitude_km,
+            body.display_name()
+        );
+        print_field(
+            &mut std::io::stdout(),
+            "Orbital Velocity",
+            format!("{:.1} m/s ({:.2} km/s)", velocity, velocity / 1000.0),
+        )?;
+        print_field(
+            &mut std::io::stdout(),
+            "Orbital Period",
+            format!(
+                "{:.1} minutes ({:.2} hours)",
+                period_minutes,
+                period_minutes / 60.0
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+// Function to handle the `distance` subcommand: the closest and farthest two bodies can be,
+// treating their orbits as circles with radius equal to the semi-major axis.
+fn run_distance(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let first = provider.get_one(matches.value_of("body1").unwrap_or_default())?;
+    let second = provider.get_one(matches.value_of("body2").unwrap_or_default())?;
+    let axis = |body: &CelestialBody| {
+        body.semimajor_axis
+            .filter(|km| *km > 0.0)
+            .ok_or_else(|| format!("No semi-major axis available for {}", body.display_name()))
+    };
+    let (closest, farthest) = physics::orbit_distance_range(axis(&first)?, axis(&second)?);
+    let is_moon = |body: &CelestialBody| body.body_type.as_deref() == Some("Moon");
+    if is_moon(&first) || is_moon(&second) {
+        warn!("a moon's semi-major axis is measured from its planet, not the Sun");
+    }
+    if matches.is_present("quiet") {
+        println!("{}", closest);
+        println!("{}", farthest);
+        return Ok(());
+    }
+    println!(
+        "Estimated distance between {} and {} (from orbital radii, ignoring eccentricity and \
+         current positions):",
+        first.display_name(),
+        second.display_name()
+    );
+    let distance = |km: f64| format!("{} km ({:.3} AU)", km, units::km_to_au(km));
+    print_field(&mut std::io::stdout(), "Closest", distance(closest))?;
+    print_field(&mut std::io::stdout(), "Farthest", distance(farthest))?;
+    Ok(())
+}
+
+// Function to handle the `phase` subcommand: the Moon's approximate phase on a date, today by
+// default. The body is still looked up so the name follows --lang and offline use is consistent.
+fn run_phase(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let date = match matches.value_of("date") {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let moon = provider.get_one("lune")?;
+    let (phase, illumination) = physics::moon_phase(date);
+    if matches.is_present("quiet") {
+        println!("{}", phase);
+        println!("{:.2}", illumination);
+    } else {
+        println!("{} on {}:", moon.display_name(), date);
+        print_field(&mut std::io::stdout(), "Phase", &phase)?;
+        print_field(
+            &mut std::io::stdout(),
+            "Illuminated",
+            format!("{:.0}%", illumination * 100.0),
+        )?;
+    }
+    Ok(())
+}
+
+// Function to handle the `gravity-at` subcommand: the pull of a body at some altitude above its
+// mean radius, following the inverse-square law.
+fn run_gravity_at(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let altitude_km: f64 = matches.value_of_t("altitude-km")?;
+    let body = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let mass_kg = mass_magnitude(&body)
+        .ok_or_else(|| format!("No mass data available for {}", body.display_name()))?;
+    let radius_m = body
+        .mean_radius
+        .filter(|radius| *radius > 0.0)
+        .ok_or_else(|| format!("No radius data available for {}", body.display_name()))?
+        * 1000.0;
+    let gravity = physics::gravity_at_altitude(mass_kg, radius_m, altitude_km * 1000.0);
+    let surface = physics::gravity_at_altitude(mass_kg, radius_m, 0.0);
+    let percent = gravity / surface * 100.0;
+    if matches.is_present("quiet") {
+        println!("{:.3}", gravity);
+        println!("{:.1}", percent);
+    } else {
+        println!(
+            "Gravity {} km above {}: {:.3} m/s² ({:.1}% of surface gravity)",
+            altitude_km,
+            body.display_name(),
+            gravity,
+            percent
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `stats` subcommand: aggregate numbers across the whole body list.
+fn run_stats(provider: &dyn BodyProvider) -> Result<(), Box<dyn std::error::Error>> {
+    let bodies = provider.get_all()?;
+    let mut out = std::io::stdout();
+    let gravities = stats::gravities(&bodies);
+    let number = |value: Option<f64>| value.map_or("N/A".to_string(), |v| format!("{:.2}", v));
+    let named = |body: Option<&CelestialBody>, value: &dyn Fn(&CelestialBody) -> String| {
+        body.map_or("N/A".to_string(), |body| {
+            format!("{} ({})", body.display_name(), value(body))
+        })
+    };
+
+    print_field(&mut out, "Bodies", bodies.len())?;
+    print_field(&mut out, "Planets", stats::planet_count(&bodies))?;
+    print_field(&mut out, "Moons", stats::moon_count(&bodies))?;
+    print_field(
+        &mut out,
+        "Mean Gravity",
+        format!("{} m/s²", number(stats::mean(&gravities))),
+    )?;
+    print_field(
+        &mut out,
+        "Median Gravity",
+        format!("{} m/s²", number(stats::median(&gravities))),
+    )?;
+    print_field(
+        &mut out,
+        "Strongest Gravity",
+        named(stats::max_by_gravity(&bodies), &|body| {
+            format!("{} m/s²", body.gravity.unwrap_or_default())
+        }),
+    )?;
+    print_field(
+        &mut out,
+        "Largest Radius",
+        named(stats::largest_by_radius(&bodies), &|body| {
+            format!("{} km", body.mean_radius.unwrap_or_default())
+        }),
+    )?;
+    print_field(
+        &mut out,
+        "Hottest",
+        named(stats::hottest(&bodies), &|body| {
+            format!("{} K", body.avg_temp.unwrap_or_default())
+        }),
+    )?;
+    print_field(
+        &mut out,
+        "Coldest",
+        named(stats::coldest(&bodies), &|body| {
+            format!("{} K", body.avg_temp.unwrap_or_default())
+        }),
+    )?;
+    Ok(())
+}
+
+// Function to handle the `similar` subcommand: the bodies closest in size to a given one.
+fn run_similar(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = provider.get_one(matches.value_of("body").unwrap_or_default())?;
+    let radius = target
+        .mean_radius
+        .filter(|radius| *radius > 0.0)
+        .ok_or_else(|| format!("No radius data available for {}", target.display_name()))?;
+    let count = matches.value_of_t("count").unwrap_or(5);
+    let bodies = provider.get_all()?;
+    let similar = closest_by_radius(&target, &bodies, count);
+    if similar.is_empty() {
+        println!("No other bodies have radius data");
+        return Ok(());
+    }
+    if !matches.is_present("quiet") {
+        println!(
+            "Closest in size to {} ({} km):",
+            target.display_name(),
+            radius
+        );
+    }
+    for body in similar {
+        let other = body.mean_radius.unwrap_or_default();
+        if matches.is_present("quiet") {
+            println!("{}", body.display_name());
+        } else {
+            print_field(
+                &mut std::io::stdout(),
+                body.display_name(),
+                format!("{} km ({:+.1} km)", other, other - radius),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Function to list the fields whose values differ between two versions of a body, as
+// (field, old value, new value) with the values rendered as compact JSON.
+fn changed_fields(old: &CelestialBody, new: &CelestialBody) -> Vec<(String, String, String)> {
+    let as_map = |body: &CelestialBody| match serde_json::to_value(body) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    let (old, new) = (as_map(old), as_map(new));
+    let keys: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let before = old.get(key).unwrap_or(&serde_json::Value::Null);
+            let after = new.get(key).unwrap_or(&serde_json::Value::Null);
+            (before != after).then(|| (snake_case(key), before.to_string(), after.to_string()))
+        })
+        .collect()
+}
+
+// Function to handle the `snapshot` subcommand: save a body's current details for `diff`.
+fn run_snapshot(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = provider.get_fresh(matches.value_of("body").unwrap_or_default())?;
+    let path = snapshot_path(&body.id).ok_or("Unable to determine the snapshot directory")?;
+    write_cache(&path, &serde_json::to_string_pretty(&body)?)
+        .map_err(|e| format!("Could not write to '{}': {}", path.display(), e))?;
+    if !matches.is_present("quiet") {
+        println!(
+            "Saved a snapshot of {} to {}",
+            body.display_name(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+// Function to handle the `diff` subcommand: fetch a body again and print the fields that
+// changed since its snapshot was saved.
+fn run_diff(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = provider.get_fresh(matches.value_of("body").unwrap_or_default())?;
+    let path = snapshot_path(&body.id).ok_or("Unable to determine the snapshot directory")?;
+    let raw = fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "No snapshot of {} found at {}; run `snapshot {}` first",
+            body.display_name(),
+            path.display(),
+            body.id
+        )
+    })?;
+    let snapshot: CelestialBody = serde_json::from_str(&raw)?;
+    let changes = changed_fields(&snapshot, &body);
+    if changes.is_empty() {
+        println!("No changes to {} since the snapshot", body.display_name());
+    }
+    for (field, old, new) in changes {
+        print_field(&mut std::io::stdout(), &field, format!("{} → {}", old, new))?;
+    }
+    Ok(())
+}
+
+// Function to collect the body names offered by shell completion: the well-known names plus
+// the ids and English names of the bundled fixture, lowercased and without duplicates.
+fn completion_body_names() -> Vec<String> {
+    let mut names: Vec<String> = KNOWN_BODY_NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    if let Ok(fixture) = serde_json::from_str::<ApiResponse>(MOCK_FIXTURE) {
+        for body in fixture.bodies {
+            names.push(body.id.to_lowercase());
+            names.push(body.english_name.to_lowercase());
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+// Function to handle the `completions` subcommand: print a completion script for a shell.
+// Bash, zsh and fish also get body names suggested for `details` and `compare`.
+fn run_completions(matches: &ArgMatches, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    let shell: Shell = matches.value_of_t("shell")?;
+    let bin = "solar_system_map_cli";
+    let mut script = Vec::new();
+    clap_complete::generate(shell, app, bin, &mut script);
+    let script = String::from_utf8(script)?;
+    let names = completion_body_names().join(" ");
+    // The zsh script ends by calling its completion function, which is swapped for a wrapper
+    // that offers body names first.
+    let entry_point = format!("\n_{} \"$@\"\n", bin);
+    match script.rfind(&entry_point) {
+        Some(index) if shell == Shell::Zsh => print!("{}", &script[..index + 1]),
+        _ => print!("{}", script),
+    }
+    match shell {
+        Shell::Bash => println!(
+            r#"
+_{bin}_bodies() {{
+    local word
+    for word in "${{COMP_WORDS[@]:1:COMP_CWORD-1}}"; do
+        if [[ ( "$word" == details || "$word" == compare ) && "${{COMP_WORDS[COMP_CWORD]}}" != -* ]]; then
+            COMPREPLY=($(compgen -W "{names}" -- "${{COMP_WORDS[COMP_CWORD]}}"))
+            return 0
+        fi
+    done
+    _{bin} "$@"
+}}
+complete -F _{bin}_bodies -o bashdefault -o default {bin}"#
+        ),
+        Shell::Zsh => println!(
+            r#"_{bin}_bodies() {{
+    if (( ${{${{words[2,CURRENT-1]}}[(I)(details|compare)]}} )) && [[ $PREFIX != -* ]]; then
+        compadd -- {names}
+        return
+    fi
+    _{bin} "$@"
+}}
+
+_{bin}_bodies "$@""#
+        ),
+        Shell::Fish => println!(
+            r#"complete -c {bin} -n "__fish_seen_subcommand_from details compare" -f -a "{names}""#
+        ),
+        _ => {}
+    }
+    Ok(())
+}
+
+// Function to handle the `search` subcommand.
+fn run_search(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let term = matches.value_of("term").unwrap_or_default();
+    let bodies = provider.get_all()?;
+    let mut found = search_bodies(term, &bodies);
+    // Sorted so that the selectors pick the same body on every run.
+    found.sort_by(|a, b| {
+        a.display_name()
+            .cmp(b.display_name())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let mut out = std::io::stdout();
+    let selected = if matches.is_present("select-first") {
+        found.first().copied()
+    } else if matches.is_present("select-last") {
+        found.last().copied()
+    } else if let Ok(index) = matches.value_of_t::<usize>("index") {
+        if index > found.len() && !found.is_empty() {
+            return Err(format!(
+                "--index {} is out of range; '{}' matched {} bodies",
+                index,
+                term,
+                found.len()
+            )
+            .into());
+        }
+        found.get(index - 1).copied()
+    } else if matches.is_present("details") && found.len() > 1 {
+        for body in &found {
+            print_body_summary(&mut out, body, false)?;
+        }
+        return Err(format!(
+            "{} bodies matched '{}'; narrow the query or pick one with --select-first, --select-last or --index",
+            found.len(),
+            term
+        )
+        .into());
+    } else if matches.is_present("details") {
+        found.first().copied()
+    } else {
+        for body in &found {
+            print_body_summary(&mut out, body, false)?;
+        }
+        None
+    };
+    let picks_one = ["select-first", "select-last", "index", "details"]
+        .iter()
+        .any(|arg| matches.is_present(arg));
+    if found.is_empty() && picks_one {
+        return Err(FetchError::NotFound {
+            name: term.to_string(),
+            suggestion: None,
+        }
+        .into());
+    } else if found.is_empty() {
+        println!("No bodies matched '{}'", term);
+    } else if let Some(body) = selected {
+        let options = DisplayOptions {
+            quiet: matches.is_present("quiet"),
+            precision: matches.value_of_t("precision").unwrap_or(3),
+            digit_separator: digit_separator(matches),
+            ..DisplayOptions::default()
+        };
+        print_body_details(&mut out, body, &options)?;
+    }
+    Ok(())
+}
+
+// Function to handle the body listing, either via `list` or when no subcommand is given.
+fn run_list(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bodies = provider.get_all()?;
+    if matches.is_present("unique") {
+        let before = bodies.len();
+        bodies = dedupe_bodies(bodies);
+        let removed = before - bodies.len();
+        if removed > 0 {
+            warn!("Removed {} duplicate or unnamed bodies", removed);
+        }
+    }
+    if matches.is_present("planets-only") {
+        bodies = filter_planets(bodies);
+    }
+    if let Some(body_type) = matches.value_of("type") {
+        let available = body_types(&bodies).join(", ");
+        bodies = filter_by_type(bodies, body_type);
+        if bodies.is_empty() {
+            warn!(
+                "No bodies of type '{}'. Types present: {}",
+                body_type, available
+            );
+        }
+    }
+    // Every --where expression has to hold; clap has already checked that they parse.
+    for expr in matches.values_of("where").into_iter().flatten() {
+        let predicate = predicate::parse_predicate(expr)?;
+        bodies.retain(|body| predicate.matches(body));
+    }
+    for field in matches.values_of("only-with-field").into_iter().flatten() {
+        bodies.retain(|body| !field_is_missing(body, field));
+    }
+    let min_mass = matches.value_of_t::<f64>("min-mass").ok();
+    let max_mass = matches.value_of_t::<f64>("max-mass").ok();
+    if min_mass.is_some() || max_mass.is_some() {
+        bodies = filter_by_mass(bodies, min_mass, max_mass);
+    }
+    // The API's own order is not stable between runs, so the listing is always sorted, by
+    // English name unless --sort-by picks another field.
+    let sort_field = sort_field(matches.value_of("sort-by").unwrap_or_default());
+    let descending = matches.is_present("desc") || matches.value_of("sort-dir") == Some("desc");
+    sort_bodies(&mut bodies, sort_field, descending);
+    if let Ok(limit) = matches.value_of_t::<usize>("limit") {
+        bodies.truncate(limit);
+    }
+    let mut out = Vec::new();
+    if matches.is_present("summary") {
+        let summary: Vec<String> = summarize_by_type(&bodies)
+            .into_iter()
+            .map(|(kind, count)| format!("{}: {}", kind, count))
+            .collect();
+        writeln!(out, "{}", summary.join(", "))?;
+        return write_output(matches.value_of("output"), &out);
+    }
+    match matches.value_of("format") {
+        Some("json") => writeln!(out, "{}", to_json(&bodies, matches)?)?,
+        Some("jsonl") => {
+            for body in &bodies {
+                writeln!(out, "{}", serde_json::to_string(body)?)?;
+            }
+        }
+        Some("yaml") => write!(out, "{}", serde_yaml::to_string(&bodies)?)?,
+        Some("gron") => writeln!(out, "{}", to_gron_list(&bodies))?,
+        Some("xml") => write!(out, "{}", to_xml(&bodies)?)?,
+        Some("csv") => print_bodies_delimited(&mut out, &bodies, ',')?,
+        Some("tsv") => print_bodies_delimited(&mut out, &bodies, '\t')?,
+        Some("markdown") => print_bodies_markdown(&mut out, &bodies)?,
+        Some("table") => {
+            let columns: Vec<&str> = matches
+                .value_of("columns")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .collect();
+            print_bodies_table(&mut out, &bodies, &columns)?
+        }
+        _ => {
+            for body in &bodies {
+                print_body_summary(&mut out, body, matches.is_present("symbols"))?;
+            }
+        }
+    }
+    let output = matches.value_of("output");
+    if matches.is_present("pager")
+        && output.is_none()
+        && std::io::stdout().is_terminal()
+        && page_output(&out)
+    {
+        return Ok(());
+    }
+    write_output(output, &out)
+}
+
+// Function to handle the `scale` subcommand.
+fn run_scale(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bodies = Vec::new();
+    for name in matches.values_of("bodies").into_iter().flatten() {
+        bodies.push(provider.get_one(name)?);
+    }
+    render_scale_bars(&bodies);
+    Ok(())
+}
+
+// Enum to represent one line typed at the interactive prompt.
+#[derive(Debug, PartialEq)]
+enum ReplCommand<'a> {
+    Empty,
+    Help,
+    List,
+    Quit,
+    Search(&'a str),
+    Details(&'a str),
+}
+
+// Function to turn a line typed at the interactive prompt into a command; anything that is not
+// a keyword is treated as a body name.
+fn parse_repl_line(line: &str) -> ReplCommand<'_> {
+    let line = line.trim();
+    let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match keyword.to_lowercase().as_str() {
+        "" => ReplCommand::Empty,
+        "help" | "?" => ReplCommand::Help,
+        "list" => ReplCommand::List,
+        "quit" | "exit" => ReplCommand::Quit,
+        "search" => ReplCommand::Search(rest.trim()),
+        _ => ReplCommand::Details(line),
+    }
+}
+
+// Function to handle the `interactive` subcommand: load the body list once, then answer
+// lookups from memory until the user quits or stdin closes.
+fn run_interactive(
+    matches: &ArgMatches,
+    provider: &dyn BodyProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bodies = provider.get_all()?;
+    let options = DisplayOptions {
+        quiet: matches.is_present("quiet"),
+        precision: matches.value_of_t("precision").unwrap_or(3),
+        digit_separator: digit_separator(matches),
+        ..DisplayOptions::default()
+    };
+    println!(
+        "Loaded {} bodies. Type a body name, `list`, `search <term>`, `help` or `quit`.",
+        bodies.len()
+    );
+    install_interrupt_handler(true)?;
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut out = std::io::stdout();
+    loop {
+        print!("{} ", ">".cyan().bold());
+        out.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        match parse_repl_line(&line) {
+            ReplCommand::Empty => {}
+            ReplCommand::Help => {
+                println!("<name>         show the details of a body");
+                println!("list           list every loaded body");
+                println!("search <term>  find bodies whose English name contains a term");
+                println!("quit           leave the prompt");
+            }
+            ReplCommand::List => {
+                for body in &bodies {
+                    print_body_summary(&mut out, body, false)?;
+                }
+            }
+            ReplCommand::Quit => break,
+            ReplCommand::Search("") => println!("Usage: search <term>"),
+            ReplCommand::Search(term) => {
+                let found = search_bodies(term, &bodies);
+                if found.is_empty() {
+                    println!("No bodies matched '{}'", term);
+                } else {
+                    for body in found {
+                        print_body_summary(&mut out, body, false)?;
+                    }
+                }
+            }
+            ReplCommand::Details(name) => match resolve_in_list(name, &bodies) {
+                Ok(body) => print_body_details(&mut out, body, &options)?,
+                Err(e) => error!("{}", e),
+            },
+        }
+    }
+    Ok(())
+}
+
+// Set by the Ctrl-C handler so the watch loop can stop between refreshes.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Function to put the terminal back to normal attributes and say goodbye after Ctrl-C.
+fn say_goodbye() {
+    if std::io::stdout().is_terminal() {
+        print!("\x1b[0m");
+    }
+    println!("\nGoodbye");
+}
+
+// Function to catch Ctrl-C in the long-running modes. The watch loop polls INTERRUPTED; the
+// interactive prompt is blocked reading stdin, so there the handler exits by itself.
+fn install_interrupt_handler(exit_immediately: bool) -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(move || {
+        INTERRUPTED.store(true, AtomicOrdering::Relaxed);
+        if exit_immediately {
+            say_goodbye();
+            std::process::exit(0);
+        }
+    })
+}
+
+// Function to run a command once, or with --watch clear the terminal and rerun it every N
+// seconds until interrupted. Failures while watching are shown and the loop carries on.
+fn watching(
+    matches: &ArgMatches,
+    run: impl Fn() -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(seconds) = matches.value_of_t::<u64>("watch") else {
+        return run();
+    };
+    install_interrupt_handler(false)?;
+    loop {
+        // Clear the screen and move the cursor to the top-left corner.
+        print!("\x1b[2J\x1b[H");
+        std::io::stdout().flush()?;
+        if let Err(e) = run() {
+            error!("{}", e);
+        }
+        // Sleep in short steps so Ctrl-C is noticed promptly.
+        let wake = Instant::now() + Duration::from_secs(seconds);
+        while Instant::now() < wake {
+            if INTERRUPTED.load(AtomicOrdering::Relaxed) {
+                say_goodbye();
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+// Function to map an error onto the process exit code: 2 when the body does not exist (or, for
+// a batch lookup, when every failed name was unknown), 1 for every other failure.
+fn exit_code(error: &(dyn std::error::Error + 'static)) -> u8 {
+    if let Some(failure) = error.downcast_ref::<PartialFailure>() {
+        return if failure.all_not_found { 2 } else { 1 };
+    }
+    match error.downcast_ref::<FetchError>() {
+        Some(FetchError::NotFound { .. }) => 2,
+        _ => 1,
+    }
+}
+
+// Function to send diagnostics to stderr through env_logger, as "Error: ..." style lines.
+// RUST_LOG overrides the default of this crate's debug messages plus everyone's warnings; main
+// narrows that to warnings once it knows whether --verbose was given.
+fn init_logger() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("warn,solar_system_map_cli=debug"),
+    )
+    .format(|buf, record| {
+        let level = match record.level() {
+            Level::Error => "Error".red(),
+            Level::Warn => "Warning".yellow(),
+            Level::Info => "Info".normal(),
+            Level::Debug | Level::Trace => "Debug".dimmed(),
+        };
+        writeln!(buf, "{}: {}", level, record.args())
+    })
+    .init();
+}
+
+// Struct to hold the config file values that become argument defaults, rendered as text
+// because clap borrows its default values.
+struct ArgDefaults {
+    retries: String,
+    timeout: String,
+    connect_timeout: String,
+    cache_ttl: Option<String>,
+}
+
+impl ArgDefaults {
+    // Function to render the config values, falling back to the built-in defaults.
+    fn from_config(config: &Config) -> ArgDefaults {
+        ArgDefaults {
+            retries: config.retries.unwrap_or(3).to_string(),
+            timeout: config.timeout.unwrap_or(30).to_string(),
+            connect_timeout: config.connect_timeout.unwrap_or(10).to_string(),
+            cache_ttl: config.cache_ttl.map(|ttl| ttl.to_string()),
+        }
+    }
+}
+
+// Function to build the command-line interface. Config file values become the argument
+// defaults, so the command line overrides them and they override the built-in defaults.
+fn build_app<'a>(config: &'a Config, defaults: &'a ArgDefaults) -> App<'a> {
+    App::new("Solar System Explorer")
+    .version("0.1.0")
+    .author("Your Name <your_email@example.com>")
+    .about("Displays information about planets and other bodies in the solar system")
+    .arg(
+        Arg::with_name("format")
+            .long("format")
+            .help("Output format for the results")
+            .takes_value(true)
+            .possible_values(FORMATS)
+            .default_value(config.format.as_deref().unwrap_or("text"))
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("pretty")
+            .long("pretty")
+            .help("Indent JSON output for reading (the default)")
+            .overrides_with("compact")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("compact")
+            .long("compact")
+            .help("Print JSON output on a single line")
+            .overrides_with("pretty")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("retries")
+            .long("retries")
+            .help("How many times to retry failed API requests")
+            .takes_value(true)
+            .default_value(&defaults.retries)
+            .validator(validators::retries)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("timeout")
+            .long("timeout")
+            .value_name("SECONDS")
+            .help("How long to wait for each API request before giving up")
+            .takes_value(true)
+            .default_value(&defaults.timeout)
+            .validator(validators::timeout)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("connect-timeout")
+            .long("connect-timeout")
+            .value_name("SECONDS")
+            .help("How long to wait for a connection to the API to be established")
+            .takes_value(true)
+            .default_value(&defaults.connect_timeout)
+            .validator(validators::timeout)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("api-base-url")
+            .long("api-base-url")
+            .value_name("URL")
+            .help("Base URL of the solar system API")
+            .takes_value(true)
+            .env("SOLAR_API_BASE")
+            .default_value(
+                config
+                    .api_base_url
+                    .as_deref()
+                    .unwrap_or("https://api.le-systeme-solaire.net/rest"),
+            )
+            .validator(validate_base_url)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("user-agent")
+            .long("user-agent")
+            .value_name("AGENT")
+            .help("User-Agent header sent to the API")
+            .takes_value(true)
+            .env("SOLAR_USER_AGENT")
+            .default_value(DEFAULT_USER_AGENT)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("offline")
+            .long("offline")
+            .help("Read bodies from the local cache instead of the API")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("mock")
+            .long("mock")
+            .help("Serve a small bundled set of bodies instead of calling the API")
+            .hide(true)
+            .global(true),
+    )
+    .arg(config_default(
+        Arg::with_name("cache-ttl")
+            .long("cache-ttl")
+            .value_name("SECONDS")
+            .help("Reuse the cached body list if it is newer than this many seconds")
+            .takes_value(true)
+            .validator(validators::cache_ttl)
+            .global(true),
+        defaults.cache_ttl.as_deref(),
+    ))
+    .arg(
+        Arg::with_name("quiet")
+            .short('q')
+            .long("quiet")
+            .help("Print bare values without labels or units")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("verbose")
+            .short('v')
+            .long("verbose")
+            .help("Log each request's URL, HTTP status and duration to stderr")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("precision")
+            .long("precision")
+            .value_name("DIGITS")
+            .help("Round numbers in the text output to this many decimal places")
+            .takes_value(true)
+            .default_value("3")
+            .validator(validators::precision)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("group-digits")
+            .long("group-digits")
+            .help("Group the digits of large numbers in the text output, e.g. 69,911")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("locale")
+            .long("locale")
+            .value_name("LOCALE")
+            .help("Group digits the way this locale does: ',' for en, '.' for the others")
+            .takes_value(true)
+            .possible_values(["en", "de", "es", "it", "nl", "pt"])
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("lang")
+            .long("lang")
+            .help("Show body names in English or in the API's native language")
+            .takes_value(true)
+            .possible_values(["en", "native"])
+            .default_value("en")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("no-color")
+            .long("no-color")
+            .help("Disable colored output (also disabled when stdout is not a terminal)")
+            .global(true),
+    )
+    .args(listing_args())
+    .subcommand(
+        SubCommand::with_name("list")
+            .about("Lists all celestial bodies (the default when no subcommand is given)")
+            .args(listing_args()),
+    )
+    .subcommand(
+        SubCommand::with_name("details")
+            .about("Displays detailed information about a specific celestial body")
+            .arg(
+                Arg::with_name("name")
+                    .help(
+                        "The names of the celestial bodies to fetch details for; \
+                         use - to read names from stdin, one per line",
+                    )
+                    .required(true)
+                    .multiple(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("relative-to-earth")
+                    .long("relative-to-earth")
+                    .help("Express mass, radius, gravity and orbital period as multiples of Earth's"),
+            )
+            .arg(
+                Arg::with_name("units")
+                    .long("units")
+                    .help("Unit system for radii, gravity, escape velocity and temperature")
+                    .takes_value(true)
+                    .possible_values(UNIT_SYSTEMS)
+                    .default_value(config.units.as_deref().unwrap_or("metric")),
+            )
+            .arg(config_default(
+                Arg::with_name("temp-unit")
+                    .long("temp-unit")
+                    .help("Temperature unit [default: kelvin, or fahrenheit with --units imperial]")
+                    .takes_value(true)
+                    .possible_values(TEMP_UNITS),
+                config.temp_unit.as_deref(),
+            ))
+            .arg(
+                Arg::with_name("fields")
+                    .long("fields")
+                    .value_name("FIELDS")
+                    .help("Comma-separated list of fields to print, e.g. gravity,mass,avg_temp")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("strict")
+                    .long("strict")
+                    .help("Print MISSING for absent fields and fail if any requested field is missing"),
+            )
+            .arg(
+                Arg::with_name("earth-similarity")
+                    .long("earth-similarity")
+                    .help("Also print the Earth Similarity Index (0.0 to 1.0)"),
+            )
+            .arg(
+                Arg::with_name("show-parent")
+                    .long("show-parent")
+                    .help("Also print the planet a moon orbits"),
+            )
+            .arg(
+                Arg::with_name("hazard-check")
+                    .long("hazard-check")
+                    .help("Also flag asteroids and comets whose orbit crosses Earth's (approximation)"),
+            )
+            .arg(
+                Arg::with_name("include-moons")
+                    .long("include-moons")
+                    .help("Also list a planet's moons"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .value_name("N")
+                    .help("Only list the first N moons with --include-moons")
+                    .takes_value(true)
+                    .requires("include-moons")
+                    .validator(validators::count),
+            )
+            .arg(watch_arg())
+            .arg(jobs_arg())
+            .arg(output_arg()),
+    )
+    .subcommand(
+        SubCommand::with_name("compare")
+            .about("Compares the numeric properties of celestial bodies side by side")
+            .arg(
+                Arg::with_name("bodies")
+                    .help("The celestial bodies to compare")
+                    .required(true)
+                    .multiple_values(true)
+                    .min_values(2)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("highlight")
+                    .long("highlight")
+                    .help("Color the largest value of each row green and the smallest red"),
+            )
+            .arg(
+                Arg::with_name("sort-columns-by")
+                    .long("sort-columns-by")
+                    .value_name("FIELD")
+                    .help("Order the columns by a field, largest first; bodies missing it go last")
+                    .takes_value(true)
+                    .possible_values(SORT_FIELDS),
+            )
+            .arg(jobs_arg()),
+    )
+    .subcommand(
+        SubCommand::with_name("parent")
+            .about("Shows the planet a moon orbits")
+            .arg(
+                Arg::with_name("moon")
+                    .help("The moon whose parent planet to show")
+                    .required(true)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("moons")
+            .about("Lists the known moons of a planet")
+            .arg(
+                Arg::with_name("planet")
+                    .help("The planet whose moons to list")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("count")
+                    .long("count")
+                    .help("Only print the number of moons"),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("weight")
+            .about("Shows what a mass would weigh on the surface of a body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to weigh the mass on")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("mass-kg")
+                    .long("mass-kg")
+                    .value_name("KG")
+                    .help("The mass to weigh, in kilograms")
+                    .takes_value(true)
+                    .default_value("70")
+                    .validator(validators::non_negative),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("jump")
+            .about("Shows how high a jump on Earth would reach on another body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to jump on")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("earth-jump-cm")
+                    .long("earth-jump-cm")
+                    .value_name("CM")
+                    .help("Height of the jump on Earth, in centimetres")
+                    .takes_value(true)
+                    .default_value("50")
+                    .validator(validators::non_negative),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("age")
+            .about("Shows how old you would be in the years of another body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body whose years to count in")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("earth-years")
+                    .long("earth-years")
+                    .value_name("YEARS")
+                    .help("Age in Earth years")
+                    .takes_value(true)
+                    .required(true)
+                    .validator(validators::non_negative),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("random")
+            .about("Shows the details of a randomly chosen body")
+            .arg(
+                Arg::with_name("planets-only")
+                    .long("planets-only")
+                    .help("Only choose among planets"),
+            )
+            .arg(
+                Arg::with_name("seed")
+                    .long("seed")
+                    .value_name("N")
+                    .help("Seed the random choice so it is the same on every run")
+                    .takes_value(true)
+                    .validator(validators::seed),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("tour")
+            .about("Walks through the planets in orbital order")
+            .arg(
+                Arg::with_name("delay")
+                    .long("delay")
+                    .value_name("MS")
+                    .help("Pause this many milliseconds before each planet after the first")
+                    .takes_value(true)
+                    .validator(validators::delay),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("schema")
+            .about("Prints the JSON Schema of the bodies emitted by --format json"),
+    )
+    .subcommand(
+        SubCommand::with_name("leaderboard")
+            .about("Ranks the planets by a property")
+            .arg(
+                Arg::with_name("metric")
+                    .help("What to rank the planets by")
+                    .required(true)
+                    .possible_values(["moons"])
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("orbit")
+            .about("Shows the speed and period of a circular orbit around a body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to orbit")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("altitude-km")
+                    .long("altitude-km")
+                    .value_name("KM")
+                    .help("Height of the orbit above the body's mean radius")
+                    .takes_value(true)
+                    .default_value("0")
+                    .validator(validators::non_negative),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("distance")
+            .about("Estimates the closest and farthest distance between two bodies")
+            .arg(
+                Arg::with_name("body1")
+                    .help("The first celestial body")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("body2")
+                    .help("The second celestial body")
+                    .required(true)
+                    .index(2),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("phase")
+            .about("Shows the Moon's approximate phase and illumination")
+            .arg(
+                Arg::with_name("date")
+                    .long("date")
+                    .value_name("YYYY-MM-DD")
+                    .help("The date to compute the phase for (defaults to today)")
+                    .takes_value(true)
+                    .validator(|value| {
+                        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                    }),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("gravity-at")
+            .about("Shows the gravitational acceleration at an altitude above a body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to measure gravity around")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("altitude-km")
+                    .long("altitude-km")
+                    .value_name("KM")
+                    .help("Height above the body's mean radius")
+                    .takes_value(true)
+                    .default_value("0")
+                    .validator(validators::non_negative),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("stats")
+            .about("Prints aggregate statistics across all bodies"),
+    )
+    .subcommand(
+        SubCommand::with_name("similar")
+            .about("Lists the bodies closest in size to a body")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to compare sizes with")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("count")
+                    .long("count")
+                    .value_name("N")
+                    .help("How many similar bodies to list")
+                    .takes_value(true)
+                    .default_value("5")
+                    .validator(validators::count),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("snapshot")
+            .about("Saves a body's current details for a later diff")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to snapshot")
+                    .required(true)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("diff")
+            .about("Shows which fields of a body changed since its snapshot")
+            .arg(
+                Arg::with_name("body")
+                    .help("The celestial body to compare with its snapshot")
+                    .required(true)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("search")
+            .about("Finds bodies whose English name contains a term")
+            .arg(
+                Arg::with_name("term")
+                    .help("The text to look for, ignoring case")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("details")
+                    .long("details")
+                    .help("Print the details of the match; fails if several bodies match and no selector picks one"),
+            )
+            .arg(
+                Arg::with_name("select-first")
+                    .long("select-first")
+                    .help("Print the details of the first match in name order")
+                    .conflicts_with_all(&["select-last", "index"]),
+            )
+            .arg(
+                Arg::with_name("select-last")
+                    .long("select-last")
+                    .help("Print the details of the last match in name order")
+                    .conflicts_with("index"),
+            )
+            .arg(
+                Arg::with_name("index")
+                    .long("index")
+                    .value_name("N")
+                    .help("Print the details of the Nth match in name order, counting from 1")
+                    .takes_value(true)
+                    .validator(validators::count),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("scale")
+            .about("Draws bars comparing the mean radii of several bodies")
+            .arg(
+                Arg::with_name("bodies")
+                    .help("The celestial bodies to draw")
+                    .required(true)
+                    .multiple_values(true)
+                    .min_values(2)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("interactive")
+            .about("Loads the body list once and opens a prompt for repeated lookups"),
+    )
+    .subcommand(
+        SubCommand::with_name("completions")
+            .about("Prints a shell completion script (bash, zsh and fish also complete body names)")
+            .arg(
+                Arg::with_name("shell")
+                    .help("The shell to generate completions for")
+                    .required(true)
+                    .possible_values(["bash", "zsh", "fish"])
+                    .index(1),
+            ),
+    )
+}
+
+// The main function sets up the command-line interface and processes user input.
+fn main() -> ExitCode {
+    init_logger();
+    let config = load_config();
+    let defaults = ArgDefaults::from_config(&config);
+    let mut app = build_app(&config, &defaults);
+    // Usage errors exit with 1 rather than clap's 2, which this tool keeps for "not found";
+    // --help and --version still print to stdout and exit with 0.
+    let matches = match app.clone().try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Files written with --output never get color codes, whatever stdout is attached to.
+    let writes_file = match matches.subcommand() {
+        Some(("list" | "details", sub)) => sub.is_present("output"),
+        Some(_) => false,
+        None => matches.is_present("output"),
+    };
+    if matches.is_present("no-color") || writes_file || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+    NATIVE_NAMES.store(
+        matches.value_of("lang") == Some("native"),
+        AtomicOrdering::Relaxed,
+    );
+    // Without RUST_LOG only warnings and errors are shown, plus request logging with --verbose.
+    if matches.is_present("verbose") {
+        log::set_max_level(LevelFilter::Debug);
+    } else if std::env::var_os("RUST_LOG").is_none() {
+        log::set_max_level(LevelFilter::Warn);
+    }
+
+    let cache_ttl = matches
+        .value_of_t::<u64>("cache-ttl")
+        .ok()
+        .map(Duration::from_secs);
+    let provider: Box<dyn BodyProvider> = if matches.is_present("mock") {
+        match MockProvider::from_fixture() {
+            Ok(provider) => Box::new(provider),
+            Err(e) => {
+                error!("Could not load the bundled fixture: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        match ReqwestProvider::new(
+            ClientSettings::from_matches(&matches),
+            matches.value_of_t("retries").unwrap_or(3),
+            matches.is_present("offline"),
+            cache_ttl,
+            matches.value_of("api-base-url").unwrap_or_default(),
+            // The --verbose request log shares stderr, and the message's line could not be
+            // cleared once log lines were written after it.
+            !matches.is_present("quiet")
+                && !matches.is_present("verbose")
+                && std::io::stderr().is_terminal(),
+        ) {
+            Ok(provider) => Box::new(provider),
+            Err(e) => {
+                error!("Could not create the HTTP client: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+    let provider = provider.as_ref();
+
+    let result = match matches.subcommand() {
+        Some(("completions", matches)) => run_completions(matches, &mut app),
+        Some(("details", matches)) => watching(matches, || run_details(matches, provider)),
+        Some(("compare", matches)) => run_compare(matches, provider),
+        Some(("moons", matches)) => run_moons(matches, provider),
+        Some(("weight", matches)) => run_weight(matches, provider),
+        Some(("jump", matches)) => run_jump(matches, provider),
+        Some(("age", matches)) => run_age(matches, provider),
+        Some(("parent", matches)) => run_parent(matches, provider),
+        Some(("random", matches)) => run_random(matches, provider),
+        Some(("tour", matches)) => run_tour(matches, provider),
+        Some(("schema", _)) => run_schema(),
+        Some(("leaderboard", _)) => run_leaderboard(provider),
+        Some(("orbit", matches)) => run_orbit(matches, provider),
+        Some(("distance", matches)) => run_distance(matches, provider),
+        Some(("phase", matches)) => run_phase(matches, provider),
+        Some(("gravity-at", matches)) => run_gravity_at(matches, provider),
+        Some(("stats", _)) => run_stats(provider),
+        Some(("similar", matches)) => run_similar(matches, provider),
+        Some(("snapshot", matches)) => run_snapshot(matches, provider),
+        Some(("diff", matches)) => run_diff(matches, provider),
+        Some(("search", matches)) => run_search(matches, provider),
+        Some(("scale", matches)) => run_scale(matches, provider),
+        Some(("list", matches)) => watching(matches, || run_list(matches, provider)),
+        Some(("interactive", matches)) => run_interactive(matches, provider),
+        _ => watching(&matches, || run_list(&matches, provider)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(exit_code(e.as_ref()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter giving every --output file written by a test its own name.
+    static OUTPUT_FILES: AtomicUsize = AtomicUsize::new(0);
+
+    // Function to load the bundled fixture that --mock serves.
+    fn fixture() -> MockProvider {
+        MockProvider::from_fixture().expect("the fixture parses")
+    }
+
+    // Function to list the English names of bodies, in order.
+    fn names(bodies: &[CelestialBody]) -> Vec<&str> {
+        bodies
+            .iter()
+            .map(|body| body.english_name.as_str())
+            .collect()
+    }
+
+    // Function to parse a command line the way main does, without a config file.
+    fn parse(args: &[&str]) -> ArgMatches {
+        let config = Config::default();
+        let defaults = ArgDefaults::from_config(&config);
+        build_app(&config, &defaults)
+            .try_get_matches_from(args)
+            .expect("a valid command line")
+    }
+
+    // Function to run `list` with the given flags against the fixture and return what it wrote.
+    fn list_output(args: &[&str]) -> String {
+        colored::control::set_override(false);
+        let path = std::env::temp_dir().join(format!(
+            "solar_system_map_cli_test_{}_{}.txt",
+            std::process::id(),
+            OUTPUT_FILES.fetch_add(1, AtomicOrdering::SeqCst)
+        ));
+        let path_text = path.to_string_lossy().to_string();
+        let mut argv = vec!["solar_system_map_cli", "list", "--output", &path_text];
+        argv.extend_from_slice(args);
+        let matches = parse(&argv);
+        let (_, list) = matches.subcommand().expect("the list subcommand");
+        run_list(list, &fixture()).expect("the listing succeeds");
+        let written = fs::read_to_string(&path).expect("the output file");
+        let _ = fs::remove_file(&path);
+        written
+    }
+
+    #[test]
+    fn mock_provider_serves_the_fixture() {
+        let provider = fixture();
+        assert_eq!(provider.get_all().unwrap().len(), 8);
+        assert_eq!(provider.get_one("mars").unwrap().english_name, "Mars");
+        assert_eq!(provider.get_one("Moon").unwrap().id, "lune");
+        assert!(provider.get_one("vulcan").is_err());
+    }
+
+    #[test]
+    fn filter_planets_keeps_only_planets() {
+        let mut planets = filter_planets(fixture().get_all().unwrap());
+        sort_bodies(&mut planets, SortField::Name, false);
+        assert_eq!(names(&planets), ["Earth", "Jupiter", "Mars", "Venus"]);
+    }
+
+    #[test]
+    fn filter_by_type_ignores_case() {
+        let moons = filter_by_type(fixture().get_all().unwrap(), "MOON");
+        assert_eq!(names(&moons), ["Moon", "Phobos"]);
+        assert!(filter_by_type(fixture().get_all().unwrap(), "Comet").is_empty());
+    }
+
+    #[test]
+    fn filter_by_mass_applies_both_bounds() {
+        let mut bodies = filter_by_mass(fixture().get_all().unwrap(), Some(1e23), Some(1e25));
+        sort_bodies(&mut bodies, SortField::Mass, false);
+        assert_eq!(names(&bodies), ["Mars", "Venus", "Earth"]);
+    }
+
+    #[test]
+    fn sort_bodies_by_gravity_descending() {
+        let mut bodies = fixture().get_all().unwrap();
+        sort_bodies(&mut bodies, SortField::Gravity, true);
+        assert_eq!(
+            names(&bodies),
+            ["Sun", "Jupiter", "Earth", "Venus", "Mars", "Moon", "Pluto", "Phobos"]
+        );
+    }
+
+    #[test]
+    fn run_list_prints_planets_sorted_by_name() {
+        assert_eq!(
+            list_output(&["--planets-only"]),
+            "Name: Earth, ID: terre, Is Planet: true\n\
+             Name: Jupiter, ID: jupiter, Is Planet: true\n\
+             Name: Mars, ID: mars, Is Planet: true\n\
+             Name: Venus, ID: venus, Is Planet: true\n"
+        );
+    }
+
+    #[test]
+    fn run_list_formats_filtered_bodies_as_json() {
+        let json = list_output(&["--type", "moon", "--format", "json"]);
+        let bodies: Vec<CelestialBody> = serde_json::from_str(&json).unwrap();
+        assert_eq!(names(&bodies), ["Moon", "Phobos"]);
+    }
+
+    #[test]
+    fn venus_rotates_retrograde() {
+        let venus = fixture().get_one("venus").unwrap();
+        assert!(is_retrograde(&venus));
+        assert!(!is_retrograde(&fixture().get_one("earth").unwrap()));
+    }
+
+    #[test]
+    fn yaml_round_trips_a_body() {
+        let venus = fixture().get_one("venus").unwrap();
+        let yaml = serde_yaml::to_string(&venus).unwrap();
+        // Absent optional fields are written out as null rather than dropped.
+        assert!(yaml.contains("moons: null"), "{}", yaml);
+        let parsed: CelestialBody = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&venus).unwrap()
+        );
+    }
+
+    // Function to pick fixture bodies by id with their gravity replaced, in the order given.
+    fn with_gravity(entries: &[(&str, Option<f64>)]) -> Vec<CelestialBody> {
+        let provider = fixture();
+        entries
+            .iter()
+            .map(|(id, gravity)| {
+                let mut body = provider.get_one(id).unwrap();
+                body.gravity = *gravity;
+                body
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compare_optional_puts_missing_values_last_in_both_directions() {
+        for descending in [false, true] {
+            assert_eq!(
+                compare_optional(Some(1.0), None, descending),
+                Ordering::Less
+            );
+            assert_eq!(
+                compare_optional(None, Some(1.0), descending),
+                Ordering::Greater
+            );
+            assert_eq!(compare_optional(None, None, descending), Ordering::Equal);
+        }
+        assert_eq!(
+            compare_optional(Some(1.0), Some(2.0), false),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_optional(Some(1.0), Some(2.0), true),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn sort_with_missing_values_is_deterministic_in_both_directions() {
+        let entries = [
+            ("phobos", None),
+            ("terre", Some(9.8)),
+            ("lune", None),
+            ("mars", Some(3.71)),
+        ];
+        let mut reversed = entries;
+        reversed.reverse();
+        for input in [entries, reversed] {
+            let mut ascending = with_gravity(&input);
+            sort_bodies(&mut ascending, SortField::Gravity, false);
+            assert_eq!(names(&ascending), ["Mars", "Earth", "Moon", "Phobos"]);
+
+            let mut descending = with_gravity(&input);
+            sort_bodies(&mut descending, SortField::Gravity, true);
+            assert_eq!(names(&descending), ["Earth", "Mars", "Moon", "Phobos"]);
+        }
+    }
+
+    #[test]
+    fn sort_breaks_ties_by_name_in_both_directions() {
+        let entries = [
+            ("terre", Some(3.71)),
+            ("mars", Some(3.71)),
+            ("venus", Some(8.87)),
+        ];
+        let mut ascending = with_gravity(&entries);
+        sort_bodies(&mut ascending, SortField::Gravity, false);
+        assert_eq!(names(&ascending), ["Earth", "Mars", "Venus"]);
+
+        let mut descending = with_gravity(&entries);
+        sort_bodies(&mut descending, SortField::Gravity, true);
+        assert_eq!(names(&descending), ["Venus", "Earth", "Mars"]);
+    }
+
+    #[test]
+    fn xml_matches_the_golden_file() {
+        // Mars has moons, written as <moon> items; Venus has none, so its null `moons` is left
+        // out entirely.
+        let provider = fixture();
+        let bodies = [
+            provider.get_one("mars").unwrap(),
+            provider.get_one("venus").unwrap(),
+        ];
+        assert_eq!(
+            to_xml(&bodies).unwrap(),
+            include_str!("../fixtures/mars_venus.xml")
+        );
+        assert!(to_xml(&bodies[1])
+            .unwrap()
+            .contains("\n<celestialBody>\n  <aphelion>"));
+    }
+
+    const OK_RESPONSE: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+
+    // Function to answer one HTTP request on a local port with `response`, returning the
+    // request's head. The request is made by `send`, which is given the server's base URL.
+    fn capture_request(response: &'static str, send: impl FnOnce(&str)) -> String {
+        use std::io::Read;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/rest", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).unwrap();
+                head.push(byte[0]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(head).unwrap()
+        });
+        send(&base_url);
+        server.join().unwrap()
+    }
+
+    // Function to make one API request with the given global flags and return its User-Agent.
+    fn user_agent_sent(flags: &[&str]) -> Option<String> {
+        let head = capture_request(OK_RESPONSE, |base_url| {
+            let mut argv = vec!["solar_system_map_cli", "--api-base-url", base_url];
+            argv.extend_from_slice(flags);
+            argv.push("list");
+            let matches = parse(&argv);
+            let settings = ClientSettings::from_matches(&matches);
+            let api = ReqwestProvider::new(settings, 1, false, None, base_url, false).unwrap();
+            get_with_retry(&api, &api.url("bodies/"), HeaderMap::new()).unwrap();
+        });
+        head.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("user-agent")
+                .then(|| value.trim().to_string())
+        })
+    }
+
+    #[test]
+    fn requests_send_the_default_user_agent() {
+        assert_eq!(
+            user_agent_sent(&[]).as_deref(),
+            Some("solar_system_map_cli/0.1.0")
+        );
+    }
+
+    #[test]
+    fn user_agent_flag_overrides_the_default() {
+        assert_eq!(
+            user_agent_sent(&["--user-agent", "demo/1.0"]).as_deref(),
+            Some("demo/1.0")
+        );
+    }
+
+    #[test]
+    fn sort_treats_zero_gravity_as_missing() {
+        let entries = [
+            ("mars", Some(3.71)),
+            ("lune", Some(0.0)),
+            ("terre", Some(9.8)),
+        ];
+        let mut ascending = with_gravity(&entries);
+        sort_bodies(&mut ascending, SortField::Gravity, false);
+        assert_eq!(names(&ascending), ["Mars", "Earth", "Moon"]);
+
+        let mut descending = with_gravity(&entries);
+        sort_bodies(&mut descending, SortField::Gravity, true);
+        assert_eq!(names(&descending), ["Earth", "Mars", "Moon"]);
+    }
+
+    #[test]
+    fn config_keeps_valid_keys_next_to_unknown_or_invalid_ones() {
+        let raw = "format = \"json\"\nretries = 2\ncompact = true\nunits = \"furlongs\"\n";
+        let (config, problems) = parse_config(raw).expect("config parses");
+        assert_eq!(config.format.as_deref(), Some("json"));
+        assert_eq!(config.retries, Some(2));
+        assert_eq!(config.units, None);
+        assert_eq!(problems.len(), 2);
+        assert!(problems
+            .iter()
+            .any(|problem| problem.starts_with("compact:")));
+        assert!(problems.iter().any(|problem| problem.starts_with("units:")));
+    }
+
+    #[test]
+    fn long_retry_after_is_reported_as_rate_limited() {
+        capture_request(
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            |base_url| {
+                let matches = parse(&["solar_system_map_cli", "list"]);
+                let settings = ClientSettings::from_matches(&matches);
+                let api = ReqwestProvider::new(settings, 3, false, None, base_url, false).unwrap();
+                let response = get_with_retry(&api, &api.url("bodies/"), HeaderMap::new()).unwrap();
+                assert!(matches!(
+                    check_status(response, None),
+                    Err(FetchError::RateLimited)
+                ));
+            },
+        );
+    }
+
+    #[test]
+    fn summarize_by_type_orders_by_count_then_name() {
+        let mut bodies = fixture().get_all().unwrap();
+        for body in bodies.iter_mut().filter(|body| body.english_name == "Sun") {
+            body.body_type = None;
+        }
+        let summary = summarize_by_type(&bodies);
+        let expected = [
+            ("Planet", 4),
+            ("Moon", 2),
+            ("Dwarf Planet", 1),
+            ("Unknown", 1),
+        ];
+        assert_eq!(
+            summary,
+            expected.map(|(kind, count)| (kind.to_string(), count))
+        );
+    }
+
+    #[test]
+    fn normalize_body_name_trims_and_lowercases() {
+        assert_eq!(normalize_body_name("  MaRs \n"), "mars");
+        assert_eq!(normalize_body_name("Terre"), "terre");
+    }
+
+    #[test]
+    fn obvious_typo_of_catches_one_edit_on_longer_names_only() {
+        assert_eq!(obvious_typo_of("jupitor"), Some("jupiter"));
+        assert_eq!(obvious_typo_of(" Jupitor "), Some("jupiter"));
+        assert_eq!(obvious_typo_of("jupiter"), None);
+        // Under five characters nothing is suggested, so "eros" does not become "eris".
+        assert_eq!(obvious_typo_of("eros"), None);
+        assert_eq!(obvious_typo_of("marz"), None);
+        assert_eq!(obvious_typo_of("jupotor"), None);
+    }
+
+    #[test]
+    fn touch_cache_restarts_the_time_to_live_without_rewriting() {
+        let path = std::env::temp_dir().join(format!(
+            "solar_system_map_cli_test_{}_touch.json",
+            std::process::id()
+        ));
+        fs::write(&path, "{}").unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+        assert!(!is_cache_fresh(&path, Duration::from_secs(60)));
+
+        touch_cache(&path).unwrap();
+        assert!(is_cache_fresh(&path, Duration::from_secs(60)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn predicates_skip_the_zero_sentinel() {
+        let predicate = predicate::parse_predicate("gravity<5").unwrap();
+        let bodies = with_gravity(&[("mars", Some(3.71)), ("lune", Some(0.0))]);
+        let matched: Vec<CelestialBody> = bodies
+            .into_iter()
+            .filter(|body| predicate.matches(body))
+            .collect();
+        assert_eq!(names(&matched), ["Mars"]);
     }
 }
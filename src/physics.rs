@@ -0,0 +1,206 @@
+// Derived physical quantities computed from the values the API provides.
+
+use crate::units::DAYS_PER_YEAR; // To turn Earth years into days.
+use chrono::NaiveDate; // To date the lunar phase.
+use std::f64::consts::PI;
+
+// Function to compute the volume of a sphere in km³ from its radius in km.
+pub fn sphere_volume(radius_km: f64) -> f64 {
+    4.0 / 3.0 * PI * radius_km.powi(3)
+}
+
+// Function to compute the surface area of a sphere in km² from its radius in km.
+pub fn sphere_surface_area(radius_km: f64) -> f64 {
+    4.0 * PI * radius_km.powi(2)
+}
+
+// Function to compute the weight, in newtons, of a mass in kg under a surface gravity in m/s².
+pub fn weight_on_body(mass_kg: f64, gravity: f64) -> f64 {
+    mass_kg * gravity
+}
+
+// Function to scale the height of a jump on Earth to a body with a different surface gravity.
+// For the same take-off speed the height is inversely proportional to gravity.
+pub fn jump_height(earth_jump_cm: f64, body_gravity: f64, earth_gravity: f64) -> f64 {
+    earth_jump_cm * earth_gravity / body_gravity
+}
+
+// Function to convert an age in Earth years to the number of orbits a body with the given
+// orbital period in days has completed over the same time.
+pub fn age_on_body(earth_years: f64, orbit_days: f64) -> f64 {
+    earth_years * DAYS_PER_YEAR / orbit_days
+}
+
+// Function to guess what a body is mostly made of from its mean density in g/cm³.
+pub fn composition_hint(density: f64) -> &'static str {
+    if density < 1.5 {
+        "gas/ice giant"
+    } else if density < 3.0 {
+        "ice and rock"
+    } else if density <= 5.0 {
+        "rocky"
+    } else {
+        "dense rock/metal"
+    }
+}
+
+// Newtonian constant of gravitation, in m³/(kg·s²).
+const GRAVITATIONAL_CONSTANT: f64 = 6.674_30e-11;
+
+// Function to compute the speed in m/s of a circular orbit of the given radius in metres
+// around a body of the given mass in kg: v = sqrt(GM/r).
+pub fn orbital_velocity(mass_kg: f64, radius_m: f64) -> f64 {
+    (GRAVITATIONAL_CONSTANT * mass_kg / radius_m).sqrt()
+}
+
+// Function to compute how many seconds one lap of a circular orbit takes, from its radius in
+// metres and its speed in m/s.
+pub fn orbital_period(radius_m: f64, velocity_mps: f64) -> f64 {
+    2.0 * PI * radius_m / velocity_mps
+}
+
+// Function to compute the gravitational acceleration in m/s² at some altitude in metres above
+// the surface of a body of the given mass in kg and radius in metres: g = GM/r².
+pub fn gravity_at_altitude(mass_kg: f64, surface_radius_m: f64, altitude_m: f64) -> f64 {
+    GRAVITATIONAL_CONSTANT * mass_kg / (surface_radius_m + altitude_m).powi(2)
+}
+
+// Function to describe in words how far a body's flattening departs from a perfect sphere.
+pub fn oblateness_description(flattening: f64) -> &'static str {
+    if flattening < 0.001 {
+        "nearly spherical"
+    } else if flattening <= 0.01 {
+        "slightly oblate"
+    } else {
+        "noticeably flattened"
+    }
+}
+
+// Function to bound the distance between two bodies on circular orbits of the given radii
+// around the same centre, returning (closest, farthest) in the same unit as the radii.
+pub fn orbit_distance_range(radius_a: f64, radius_b: f64) -> (f64, f64) {
+    ((radius_a - radius_b).abs(), radius_a + radius_b)
+}
+
+// Function to compute an orbit's eccentricity from its farthest and closest distances to the
+// body it orbits.
+pub fn eccentricity_from_apsides(aphelion: f64, perihelion: f64) -> f64 {
+    (aphelion - perihelion) / (aphelion + perihelion)
+}
+
+// Earth's aphelion in AU; a perihelion inside it means the orbit can reach Earth's distance.
+pub const EARTH_APHELION_AU: f64 = 1.017;
+
+// Function to flag an asteroid or comet whose perihelion lies inside Earth's aphelion. This is a
+// simplified take on the near-Earth object criteria that ignores inclination and the true
+// minimum orbit intersection distance, so it is only an approximation.
+pub fn is_earth_crossing(perihelion_au: f64, body_type: &str) -> bool {
+    is_small_body(body_type) && perihelion_au > 0.0 && perihelion_au < EARTH_APHELION_AU
+}
+
+// Function to check whether a body type names an asteroid or a comet.
+pub fn is_small_body(body_type: &str) -> bool {
+    body_type.eq_ignore_ascii_case("asteroid") || body_type.eq_ignore_ascii_case("comet")
+}
+
+// Function to compute the sunlight a body receives relative to Earth from its distance to the
+// Sun in AU, by the inverse-square law.
+pub fn solar_flux_relative(semimajor_axis_au: f64) -> f64 {
+    1.0 / (semimajor_axis_au * semimajor_axis_au)
+}
+
+// Mean length of the lunar synodic month (new moon to new moon), in days.
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+
+// Function to estimate the Moon's phase on a date (taken at noon UTC), returning the phase name
+// and the illuminated fraction of the disc from 0 to 1. Ages are counted from the new moon of
+// 2000-01-06 18:14 UTC, which keeps the estimate within about a day of the true phase.
+pub fn moon_phase(date: NaiveDate) -> (String, f64) {
+    let reference = NaiveDate::from_ymd_opt(2000, 1, 6).expect("valid reference date");
+    let days_since = (date - reference).num_days() as f64 + 0.5 - (18.0 + 14.0 / 60.0) / 24.0;
+    let age = days_since.rem_euclid(SYNODIC_MONTH_DAYS);
+    let illumination = (1.0 - (2.0 * PI * age / SYNODIC_MONTH_DAYS).cos()) / 2.0;
+    let names = [
+        "New Moon",
+        "Waxing Crescent",
+        "First Quarter",
+        "Waxing Gibbous",
+        "Full Moon",
+        "Waning Gibbous",
+        "Last Quarter",
+        "Waning Crescent",
+    ];
+    // Each named phase covers an eighth of the month, centred on its exact moment.
+    let index = ((age / SYNODIC_MONTH_DAYS * 8.0 + 0.5).floor() as usize) % names.len();
+    (names[index].to_string(), illumination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Function to compare floats to within a relative tolerance.
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            ((actual - expected) / expected).abs() < tolerance,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn sphere_volume_matches_earth() {
+        // Earth's volume is about 1.08321e12 km³.
+        assert_close(sphere_volume(6371.0084), 1.08321e12, 1e-4);
+    }
+
+    #[test]
+    fn sphere_surface_area_matches_earth() {
+        // Earth's surface area is about 5.10072e8 km².
+        assert_close(sphere_surface_area(6371.0084), 5.10072e8, 1e-4);
+    }
+
+    #[test]
+    fn composition_hint_boundaries() {
+        assert_eq!(composition_hint(1.49), "gas/ice giant");
+        assert_eq!(composition_hint(1.5), "ice and rock");
+        assert_eq!(composition_hint(2.99), "ice and rock");
+        assert_eq!(composition_hint(3.0), "rocky");
+        // 5.0 itself still counts as rocky; only denser bodies are metal-rich.
+        assert_eq!(composition_hint(5.0), "rocky");
+        assert_eq!(composition_hint(5.01), "dense rock/metal");
+    }
+
+    #[test]
+    fn moon_phase_on_a_known_new_moon() {
+        // The new moon of the total solar eclipse of 2024-04-08.
+        let (name, illumination) = moon_phase(NaiveDate::from_ymd_opt(2024, 4, 8).unwrap());
+        assert_eq!(name, "New Moon");
+        assert!(illumination < 0.05, "illumination {}", illumination);
+    }
+
+    #[test]
+    fn moon_phase_on_a_known_full_moon() {
+        let (name, illumination) = moon_phase(NaiveDate::from_ymd_opt(2024, 4, 23).unwrap());
+        assert_eq!(name, "Full Moon");
+        assert!(illumination > 0.95, "illumination {}", illumination);
+    }
+
+    #[test]
+    fn jump_height_on_mars() {
+        // A 50 cm jump on Earth (9.8 m/s²) reaches about 132 cm on Mars (3.71 m/s²).
+        assert_close(jump_height(50.0, 3.71, 9.8), 132.08, 1e-3);
+    }
+
+    #[test]
+    fn eccentricity_from_apsides_matches_mars() {
+        // Mars swings between 206.6 and 249.2 million km from the Sun; its eccentricity is 0.0934.
+        assert_close(eccentricity_from_apsides(249.2e6, 206.6e6), 0.0934, 1e-2);
+    }
+
+    #[test]
+    fn eccentricity_from_apsides_of_a_circle_is_zero() {
+        assert_eq!(eccentricity_from_apsides(1.0, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,134 @@
+// Simple `--where` filters such as `gravity>5` or `is_planet=true` for the listing.
+
+use crate::{mass_magnitude, CelestialBody};
+use std::cmp::Ordering;
+
+// Fields that can appear on the left of a `--where` comparison.
+pub const PREDICATE_FIELDS: [&str; 12] = [
+    "mass",
+    "density",
+    "gravity",
+    "escape",
+    "mean_radius",
+    "semimajor_axis",
+    "sideral_orbit",
+    "avg_temp",
+    "is_planet",
+    "body_type",
+    "id",
+    "english_name",
+];
+
+// Enum to describe the comparison a predicate makes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Greater,
+    Less,
+    GreaterOrEqual,
+    LessOrEqual,
+    Equal,
+}
+
+// Struct to hold one parsed `--where` expression.
+#[derive(Debug, Clone)]
+pub struct Predicate {
+    field: String,
+    operator: Operator,
+    value: String,
+}
+
+// Enum to hold a field's value in a form that can be compared.
+enum FieldData {
+    Number(f64),
+    Flag(bool),
+    Text(String),
+}
+
+// Function to parse an expression of the form `<field><operator><value>`.
+pub fn parse_predicate(expr: &str) -> Result<Predicate, String> {
+    let start = expr
+        .find(['>', '<', '='])
+        .ok_or_else(|| format!("'{}' has no operator; use one of >, <, >=, <=, =", expr))?;
+    let (operator, width) = match (&expr[start..start + 1], expr[start + 1..].starts_with('=')) {
+        (">", true) => (Operator::GreaterOrEqual, 2),
+        ("<", true) => (Operator::LessOrEqual, 2),
+        (">", false) => (Operator::Greater, 1),
+        ("<", false) => (Operator::Less, 1),
+        _ => (Operator::Equal, 1),
+    };
+    let field = expr[..start].trim().to_lowercase();
+    let value = expr[start + width..].trim().to_string();
+    if !PREDICATE_FIELDS.contains(&field.as_str()) {
+        return Err(format!(
+            "Unknown field '{}'. Valid fields are: {}",
+            field,
+            PREDICATE_FIELDS.join(", ")
+        ));
+    }
+    if value.is_empty() {
+        return Err(format!("'{}' has no value to compare with", expr));
+    }
+    let numeric = !matches!(
+        field.as_str(),
+        "is_planet" | "body_type" | "id" | "english_name"
+    );
+    if numeric && value.parse::<f64>().is_err() {
+        return Err(format!("'{}' needs a number after the operator", expr));
+    }
+    if !numeric && operator != Operator::Equal {
+        return Err(format!("'{}' can only be compared with =", field));
+    }
+    if field == "is_planet" && value.parse::<bool>().is_err() {
+        return Err("is_planet can only be true or false".to_string());
+    }
+    Ok(Predicate {
+        field,
+        operator,
+        value,
+    })
+}
+
+// Function to read the value a predicate refers to; None when the body lacks it. The API uses 0
+// for an unknown number, so zero and below count as missing too.
+fn field_data(body: &CelestialBody, field: &str) -> Option<FieldData> {
+    let number = |value: Option<f64>| value.filter(|v| *v > 0.0).map(FieldData::Number);
+    match field {
+        "mass" => number(mass_magnitude(body)),
+        "density" => number(body.density),
+        "gravity" => number(body.gravity),
+        "escape" => number(body.escape),
+        "mean_radius" => number(body.mean_radius),
+        "semimajor_axis" => number(body.semimajor_axis),
+        "sideral_orbit" => number(body.sideral_orbit),
+        "avg_temp" => number(body.avg_temp.map(f64::from)),
+        "is_planet" => Some(FieldData::Flag(body.is_planet)),
+        "body_type" => body.body_type.clone().map(FieldData::Text),
+        "id" => Some(FieldData::Text(body.id.clone())),
+        _ => Some(FieldData::Text(body.english_name.clone())),
+    }
+}
+
+impl Predicate {
+    // Function to check whether a body satisfies the predicate. Bodies missing the field never
+    // match.
+    pub fn matches(&self, body: &CelestialBody) -> bool {
+        match field_data(body, &self.field) {
+            Some(FieldData::Number(actual)) => {
+                let Ok(expected) = self.value.parse::<f64>() else {
+                    return false;
+                };
+                let ordering = actual.total_cmp(&expected);
+                match self.operator {
+                    Operator::Greater => ordering == Ordering::Greater,
+                    Operator::Less => ordering == Ordering::Less,
+                    Operator::GreaterOrEqual => ordering != Ordering::Less,
+                    Operator::LessOrEqual => ordering != Ordering::Greater,
+                    Operator::Equal => ordering == Ordering::Equal,
+                }
+            }
+            Some(FieldData::Flag(actual)) => self.value.parse::<bool>() == Ok(actual),
+            Some(FieldData::Text(actual)) => actual.eq_ignore_ascii_case(&self.value),
+            None => false,
+        }
+    }
+}
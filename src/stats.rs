@@ -0,0 +1,99 @@
+// Aggregate statistics across a list of bodies. A body missing the value a statistic needs is
+// left out of that statistic; the API reports unknown gravity, radius and temperature as 0.
+
+use crate::CelestialBody;
+
+// Function to keep a measurement only when the API actually provided one.
+fn known(value: Option<f64>) -> Option<f64> {
+    value.filter(|v| *v > 0.0)
+}
+
+// Function to find the body with the largest value of a measurement.
+fn max_by<F>(bodies: &[CelestialBody], value: F) -> Option<&CelestialBody>
+where
+    F: Fn(&CelestialBody) -> Option<f64>,
+{
+    bodies
+        .iter()
+        .filter_map(|body| known(value(body)).map(|v| (body, v)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(body, _)| body)
+}
+
+// Function to find the body with the smallest value of a measurement.
+fn min_by<F>(bodies: &[CelestialBody], value: F) -> Option<&CelestialBody>
+where
+    F: Fn(&CelestialBody) -> Option<f64>,
+{
+    bodies
+        .iter()
+        .filter_map(|body| known(value(body)).map(|v| (body, v)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(body, _)| body)
+}
+
+// Function to collect the known surface gravities, in m/s².
+pub fn gravities(bodies: &[CelestialBody]) -> Vec<f64> {
+    bodies
+        .iter()
+        .filter_map(|body| known(body.gravity))
+        .collect()
+}
+
+// Function to compute the arithmetic mean of some values.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+// Function to compute the median of some values, averaging the middle two of an even count.
+pub fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+        _ => Some(sorted[middle]),
+    }
+}
+
+// Function to find the body with the strongest surface gravity.
+pub fn max_by_gravity(bodies: &[CelestialBody]) -> Option<&CelestialBody> {
+    max_by(bodies, |body| body.gravity)
+}
+
+// Function to find the body with the largest mean radius.
+pub fn largest_by_radius(bodies: &[CelestialBody]) -> Option<&CelestialBody> {
+    max_by(bodies, |body| body.mean_radius)
+}
+
+// Function to find the body with the highest average temperature.
+pub fn hottest(bodies: &[CelestialBody]) -> Option<&CelestialBody> {
+    max_by(bodies, |body| body.avg_temp.map(f64::from))
+}
+
+// Function to find the body with the lowest average temperature.
+pub fn coldest(bodies: &[CelestialBody]) -> Option<&CelestialBody> {
+    min_by(bodies, |body| body.avg_temp.map(f64::from))
+}
+
+// Function to count the planets.
+pub fn planet_count(bodies: &[CelestialBody]) -> usize {
+    bodies.iter().filter(|body| body.is_planet).count()
+}
+
+// Function to count the moons, going by the body type the API reports.
+pub fn moon_count(bodies: &[CelestialBody]) -> usize {
+    bodies
+        .iter()
+        .filter(|body| {
+            body.body_type
+                .as_deref()
+                .is_some_and(|kind| kind.eq_ignore_ascii_case("moon"))
+        })
+        .count()
+}
@@ -0,0 +1,99 @@
+// Conversion functions between the API's metric units and imperial units.
+
+const KM_PER_MILE: f64 = 1.609344;
+const METERS_PER_FOOT: f64 = 0.3048;
+const MPS_PER_MPH: f64 = 0.44704;
+pub const DAYS_PER_YEAR: f64 = 365.25;
+const KM_PER_AU: f64 = 149_597_871.0;
+
+// Function to convert kilometres to miles.
+pub fn km_to_miles(km: f64) -> f64 {
+    km / KM_PER_MILE
+}
+
+// Function to convert an acceleration in m/s² to ft/s².
+pub fn mps2_to_fps2(mps2: f64) -> f64 {
+    mps2 / METERS_PER_FOOT
+}
+
+// Function to convert a speed in m/s to miles per hour.
+pub fn mps_to_mph(mps: f64) -> f64 {
+    mps / MPS_PER_MPH
+}
+
+// Function to convert a temperature in Kelvin to degrees Fahrenheit.
+pub fn kelvin_to_fahrenheit(kelvin: i32) -> f64 {
+    f64::from(kelvin) * 9.0 / 5.0 - 459.67
+}
+
+// Function to convert a temperature in Kelvin to degrees Celsius.
+pub fn kelvin_to_celsius(kelvin: i32) -> f64 {
+    f64::from(kelvin) - 273.15
+}
+
+// Function to convert a duration in days to Julian years of 365.25 days.
+pub fn days_to_years(days: f64) -> f64 {
+    days / DAYS_PER_YEAR
+}
+
+// Function to convert kilometres to astronomical units.
+pub fn km_to_au(km: f64) -> f64 {
+    km / KM_PER_AU
+}
+
+// Function to convert a duration in hours to Earth days.
+pub fn hours_to_days(hours: f64) -> f64 {
+    hours / 24.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Function to compare floats that went through a unit conversion.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn km_to_miles_uses_the_international_mile() {
+        assert_close(km_to_miles(1.609344), 1.0);
+        assert_close(km_to_miles(6371.0), 3958.7558657);
+    }
+
+    #[test]
+    fn gravity_converts_to_feet_per_second_squared() {
+        assert_close(mps2_to_fps2(0.3048), 1.0);
+        assert_close(mps2_to_fps2(9.80665), 32.1740486);
+    }
+
+    #[test]
+    fn speed_converts_to_miles_per_hour() {
+        assert_close(mps_to_mph(0.44704), 1.0);
+        assert_close(mps_to_mph(11186.0), 25022.369363);
+    }
+
+    #[test]
+    fn kelvin_converts_at_absolute_zero() {
+        assert_close(kelvin_to_celsius(0), -273.15);
+        assert_close(kelvin_to_fahrenheit(0), -459.67);
+    }
+
+    #[test]
+    fn kelvin_converts_at_room_temperature() {
+        assert_close(kelvin_to_celsius(293), 19.85);
+        assert_close(kelvin_to_fahrenheit(293), 67.73);
+    }
+
+    #[test]
+    fn days_convert_to_julian_years() {
+        assert_close(days_to_years(365.25), 1.0);
+        // Neptune's orbital period.
+        assert_close(days_to_years(60190.0), 164.7912389);
+    }
+}
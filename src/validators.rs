@@ -0,0 +1,75 @@
+// Checks for numeric command-line values, used as clap validators so bad input is rejected
+// with a clear message before anything runs.
+
+// Largest number of bodies fetched at the same time, to avoid hammering the API.
+pub const MAX_JOBS: usize = 16;
+
+// Largest number of retries of a failed request.
+pub const MAX_RETRIES: u64 = 10;
+
+// Largest number of decimal places numbers are rounded to; an f64 holds no more than this.
+pub const MAX_PRECISION: u64 = 17;
+
+// Function to check that a value is a whole number between `min` and `max` inclusive.
+fn whole_number(value: &str, min: u64, max: u64, what: &str) -> Result<(), String> {
+    match value.trim().parse::<u64>() {
+        Ok(n) if n < min => Err(format!("{} must be at least {}", what, min)),
+        Ok(n) if n > max => Err(format!("{} must be at most {}", what, max)),
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} must be a whole number, not '{}'", what, value)),
+    }
+}
+
+// Function to check a count such as --limit, which must be at least one.
+pub fn count(value: &str) -> Result<(), String> {
+    whole_number(value, 1, u64::MAX, "the count")
+}
+
+// Function to check --retries.
+pub fn retries(value: &str) -> Result<(), String> {
+    whole_number(value, 1, MAX_RETRIES, "the number of retries")
+}
+
+// Function to check --jobs.
+pub fn jobs(value: &str) -> Result<(), String> {
+    whole_number(value, 1, MAX_JOBS as u64, "the number of jobs")
+}
+
+// Function to check a timeout in seconds, which cannot be zero.
+pub fn timeout(value: &str) -> Result<(), String> {
+    whole_number(value, 1, u64::MAX, "the timeout")
+}
+
+// Function to check a refresh interval in seconds, which cannot be zero.
+pub fn interval(value: &str) -> Result<(), String> {
+    whole_number(value, 1, u64::MAX, "the interval")
+}
+
+// Function to check --precision, the number of decimal places.
+pub fn precision(value: &str) -> Result<(), String> {
+    whole_number(value, 0, MAX_PRECISION, "the precision")
+}
+
+// Function to check --cache-ttl in seconds, where zero means the cache is never fresh.
+pub fn cache_ttl(value: &str) -> Result<(), String> {
+    whole_number(value, 0, u64::MAX, "the cache time-to-live")
+}
+
+// Function to check a delay in milliseconds, which may be zero.
+pub fn delay(value: &str) -> Result<(), String> {
+    whole_number(value, 0, u64::MAX, "the delay")
+}
+
+// Function to check --seed, which can be any whole number.
+pub fn seed(value: &str) -> Result<(), String> {
+    whole_number(value, 0, u64::MAX, "the seed")
+}
+
+// Function to check a measurement such as a height or mass, which cannot be negative.
+pub fn non_negative(value: &str) -> Result<(), String> {
+    match value.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(()),
+        Ok(_) => Err("the value must be zero or more".to_string()),
+        Err(_) => Err(format!("'{}' is not a number", value)),
+    }
+}